@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+/// search statistics sent by the worker thread when an AI starts and finishes its search,
+/// engines fill the values they report in between, Magister Ludi only reports its simulations and the time
+#[derive(Clone, Default)]
+pub struct SearchInfo {
+    /// the searching player is the blue one
+    pub for_black: bool,
+    /// the search is done and the move was sent
+    pub finished: bool,
+    pub simulations: usize,
+    pub elapsed: Duration,
+    /// chance of the searching player to win
+    pub win_rate: Option<f64>,
+    /// expected moves of both players in standard notation
+    pub principal_variation: Vec<String>,
+}
+
+impl SearchInfo {
+    pub fn simulations_per_second(&self) -> Option<f64> {
+        if !self.finished || self.elapsed.is_zero() {
+            return None;
+        }
+        Some(self.simulations as f64 / self.elapsed.as_secs_f64())
+    }
+}
+
+/// chance of the blue player to win, taken from the engine or estimated by the lost marbles
+pub fn blue_evaluation(info: Option<&SearchInfo>, black_loss: u8, white_loss: u8) -> (f32, bool) {
+    match info.and_then(|info| info.win_rate.map(|win_rate| (info.for_black, win_rate))) {
+        Some((for_black, win_rate)) => {
+            let win_rate = win_rate.clamp(0.0, 1.0) as f32;
+            (if for_black {win_rate} else {1.0 - win_rate}, true)
+        }
+        None => {
+            // six lost marbles end the game
+            let balance = f32::from(white_loss) - f32::from(black_loss);
+            (0.5 + balance / 12.0, false)
+        }
+    }
+}
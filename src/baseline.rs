@@ -0,0 +1,162 @@
+use rustai_abalone::game::{AbaloneGame, Board, Coord};
+
+use crate::notation::selections;
+use crate::side::SidedGame;
+
+/// seed of the random player, it chooses the same moves in every game
+const RANDOM_SEED: u64 = 0x2545_f491_4f6c_dd1d;
+/// field in the middle of the board
+const CENTER: Coord = Coord { x: 5, y: 5 };
+
+/// all states the player to move can reach, always in the same order
+pub fn possible_moves(game: &SidedGame) -> Vec<Board> {
+    let mut next_states = Vec::new();
+    for selection in selections(game) {
+        let moves = game.calc_coord_moves(selection);
+        // the directions are taken in a fixed order, as the map has none
+        for marb_move in AbaloneGame::get_game_moves() {
+            if let Some(next_state) = moves.get(&marb_move) {
+                if !next_states.contains(next_state) {
+                    next_states.push(*next_state);
+                }
+            }
+        }
+    }
+    next_states
+}
+
+/// number of moves a marble needs to reach the center
+fn center_distance(coord: Coord) -> i32 {
+    let (dx, dy) = (coord.x as i32 - CENTER.x as i32, coord.y as i32 - CENTER.y as i32);
+    dx.abs().max(dy.abs()).max((dx + dy).abs())
+}
+
+/// plays a random possible move, the moves follow from the seed
+pub struct RandomPlayer {
+    /// state of the xorshift generator
+    rng_state: u64,
+}
+
+impl RandomPlayer {
+    pub fn new(seed: u64) -> Self {
+        // the generator must not start at zero
+        Self { rng_state: seed.max(1) }
+    }
+
+    fn next_random(&mut self) -> u64 {
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 7;
+        self.rng_state ^= self.rng_state << 17;
+        self.rng_state
+    }
+
+    /// `None` if the player to move cannot move
+    pub fn choose(&mut self, game: &SidedGame) -> Option<Board> {
+        let next_states = possible_moves(game);
+        if next_states.is_empty() {
+            return None;
+        }
+        let index = (self.next_random() % next_states.len() as u64) as usize;
+        Some(next_states[index])
+    }
+}
+
+impl Default for RandomPlayer {
+    fn default() -> Self {
+        Self::new(RANDOM_SEED)
+    }
+}
+
+/// chooses the move that ejects the most marbles, then the one with the own marbles closest to the center
+/// and the opponent marbles furthest from it, equal moves are decided by their order
+pub fn greedy_move(game: &SidedGame) -> Option<Board> {
+    let own_is_black = game.get_black_tomove();
+    let (blacks, whites, _) = game.get_coords_by_type();
+    let opponent_marbles = if own_is_black {whites.len()} else {blacks.len()};
+    let mut best: Option<((usize, i32), Board)> = None;
+    for next_state in possible_moves(game) {
+        let (blacks, whites, _) = AbaloneGame::coords_by_type(next_state);
+        let (own, opponent) = if own_is_black {(blacks, whites)} else {(whites, blacks)};
+        let ejected = opponent_marbles.saturating_sub(opponent.len());
+        // the board may be turned, which keeps the distances to the center
+        let center_control = opponent.iter().map(|coord| center_distance(*coord)).sum::<i32>()
+            - own.iter().map(|coord| center_distance(*coord)).sum::<i32>();
+        let score = (ejected, center_control);
+        if best.is_none_or(|(best_score, _)| score > best_score) {
+            best = Some((score, next_state));
+        }
+    }
+    best.map(|(_, next_state)| next_state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustai_abalone::game::{BELGIAN_DAISY, EMPTY_BOARD};
+    use crate::history::MoveHistory;
+    use crate::record::{BLACK_VALUE, WHITE_VALUE};
+
+    /// blue marbles on the bottom rows and yellow marbles on the top rows, too far apart to push each other
+    fn separated_board() -> Board {
+        let mut board = EMPTY_BOARD;
+        for y in 1..=5 {
+            board[9][y] = BLACK_VALUE;
+            board[1][y + 4] = WHITE_VALUE;
+        }
+        for y in 1..=4 {
+            board[8][y] = BLACK_VALUE;
+            board[2][y + 4] = WHITE_VALUE;
+        }
+        board
+    }
+
+    fn own_distances(state: Board, black: bool) -> i32 {
+        let (blacks, whites, _) = AbaloneGame::coords_by_type(state);
+        let own = if black {blacks} else {whites};
+        own.iter().map(|coord| center_distance(*coord)).sum()
+    }
+
+    fn random_game(player: &mut RandomPlayer) -> Vec<Board> {
+        let mut game = SidedGame::new(BELGIAN_DAISY, true);
+        let mut states = Vec::new();
+        for _ in 0..10 {
+            let black_moved = game.get_black_tomove();
+            let next_state = player.choose(&game).expect("the game is not over after a few moves");
+            MoveHistory::apply_state(&mut game, next_state, black_moved);
+            states.push(next_state);
+        }
+        states
+    }
+
+    #[test]
+    fn random_player_repeats_its_moves() {
+        assert_eq!(random_game(&mut RandomPlayer::default()), random_game(&mut RandomPlayer::default()));
+        assert_eq!(random_game(&mut RandomPlayer::new(7)), random_game(&mut RandomPlayer::new(7)));
+    }
+
+    #[test]
+    fn greedy_player_ejects_marbles_first() {
+        let mut board = separated_board();
+        // three blue marbles below the two yellow marbles of the left diagonal can push one of them off the board,
+        // although this leads them away from the center
+        board[8][4] = EMPTY_BOARD[8][4];
+        for row in &mut board[3..=5] {
+            row[5] = BLACK_VALUE;
+        }
+        let game = SidedGame::new(board, true);
+        let next_state = greedy_move(&game).unwrap();
+        let (_, whites, _) = AbaloneGame::coords_by_type(next_state);
+        assert_eq!(whites.len(), 8);
+    }
+
+    #[test]
+    fn greedy_player_takes_the_center() {
+        for black_tomove in [true, false] {
+            let game = SidedGame::new(separated_board(), black_tomove);
+            let next_state = greedy_move(&game).unwrap();
+            let best = possible_moves(&game).into_iter().map(|state| own_distances(state, black_tomove)).min().unwrap();
+            assert_eq!(own_distances(next_state, black_tomove), best);
+            assert!(best < own_distances(separated_board(), black_tomove));
+        }
+    }
+}
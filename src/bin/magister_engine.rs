@@ -0,0 +1,267 @@
+use crossbeam_channel::{select, unbounded, Receiver, Sender};
+use rustai_abalone::game::{AbaloneGame, Board};
+use std::collections::VecDeque;
+use std::io::BufRead;
+use std::{thread, time};
+use play_abalone::engine::EngineCommand;
+use play_abalone::history::MoveHistory;
+use play_abalone::notation::{find_move, state_to_notation};
+use play_abalone::player_setting::PlayerSetting;
+use play_abalone::position::parse_position;
+use play_abalone::side::{fit_simulations, needs_new_instance, SidedMagister};
+
+const USAGE: &str = "usage: magister_engine
+
+Plays the Magister Ludi AI over the engine protocol of play_abalone on stdin and stdout.
+The options simulations, threads, minimum and depth are the parameters of the AI.";
+
+/// result of a search thread: the AI, its chosen state and the time it took
+type SearchResult = (SidedMagister, Board, time::Duration);
+
+/// parameters of the AI, set with `setoption`
+struct MagisterOptions {
+    simulations: usize,
+    threads: usize,
+    minimum: usize,
+    depth: usize,
+}
+
+impl MagisterOptions {
+    fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let value: usize = value.parse().map_err(|_| format!("option {} needs a number, not '{}'", name, value))?;
+        match name {
+            "simulations" => self.simulations = value.max(1),
+            "threads" => self.threads = value.max(1),
+            "minimum" => self.minimum = value.max(1),
+            "depth" => self.depth = value,
+            _ => return Err(format!("unknown option '{}'", name)),
+        }
+        Ok(())
+    }
+}
+
+impl Default for MagisterOptions {
+    /// the AI offered in the start window of the game
+    fn default() -> Self {
+        match PlayerSetting::default_ai() {
+            PlayerSetting::MagisterLudiAI { mcts_num, mcts_parallel, mcts_minimum, mcts_depth, .. } => {
+                Self { simulations: mcts_num, threads: mcts_parallel, minimum: mcts_minimum, depth: mcts_depth }
+            }
+            _ => Self { simulations: 200, threads: 12, minimum: 7, depth: 0 },
+        }
+    }
+}
+
+/// the AI and the game it plays, the search tree is kept as long as the positions follow each other
+struct MagisterEngine {
+    options: MagisterOptions,
+    /// missing while it searches
+    magister: Option<SidedMagister>,
+    /// simulations of the current Magister Ludi instance
+    current_num: usize,
+    /// measured thinking time per simulation
+    simulation_time: Option<f64>,
+    /// position to search with the next `go`
+    position: Option<(Board, bool)>,
+    /// game state after the last own move and whether the engine played blue
+    last_move: Option<(Board, bool)>,
+    /// position and simulations of the running search
+    search: Option<(Board, bool, usize)>,
+    results: Sender<SearchResult>,
+}
+
+impl MagisterEngine {
+    fn new(results: Sender<SearchResult>) -> Self {
+        let options = MagisterOptions::default();
+        let current_num = options.simulations;
+        Self { options, magister: None, current_num, simulation_time: None, position: None, last_move: None, search: None, results }
+    }
+
+    fn is_searching(&self) -> bool {
+        self.search.is_some()
+    }
+
+    fn handle(&mut self, command: EngineCommand) -> Result<(), String> {
+        match command {
+            EngineCommand::Aei => {
+                println!("id name Magister Ludi");
+                println!("id author Harald Locke");
+                println!("option name simulations default {}", self.options.simulations);
+                println!("option name threads default {}", self.options.threads);
+                println!("option name minimum default {}", self.options.minimum);
+                println!("option name depth default {}", self.options.depth);
+                println!("aeiok");
+            }
+            EngineCommand::SetOption { name, value } => {
+                self.options.set(&name, &value)?;
+                // the AI is created again with the new parameters
+                self.new_game();
+            }
+            EngineCommand::IsReady => println!("readyok"),
+            EngineCommand::NewGame => self.new_game(),
+            EngineCommand::Position(text) => self.set_position(&text)?,
+            EngineCommand::Go { movetime, nodes } => self.go(movetime, nodes)?,
+            // a Magister Ludi search cannot be interrupted, its move is sent as soon as it is done
+            EngineCommand::Stop => {}
+            EngineCommand::Quit => {}
+        }
+        Ok(())
+    }
+
+    fn new_game(&mut self) {
+        if let Some(mut magister) = self.magister.take() {
+            magister.stop_execution();
+        }
+        self.position = None;
+        self.last_move = None;
+    }
+
+    fn set_position(&mut self, text: &str) -> Result<(), String> {
+        let (board, black_tomove) = parse_position(text).map_err(|err| err.to_string())?;
+        // a move of the opponent after the own move lets the AI keep its search tree
+        let follows = self.last_move.is_some_and(|(state, engine_is_black)| {
+            if engine_is_black != black_tomove {
+                return false;
+            }
+            let game = MoveHistory::new_game(state, !black_tomove);
+            // the board is turned after every move of the blue player
+            let next_state = if black_tomove {board} else {AbaloneGame::rotate_board(board)};
+            find_move(&game, next_state).is_some()
+        });
+        match self.magister.as_mut() {
+            Some(magister) if follows => magister.external_move(board),
+            _ => {
+                if let Some(mut magister) = self.magister.take() {
+                    magister.stop_execution();
+                }
+                self.magister = Some(self.create_magister(board, black_tomove, self.options.simulations));
+            }
+        }
+        self.last_move = None;
+        self.position = Some((board, black_tomove));
+        Ok(())
+    }
+
+    fn create_magister(&mut self, state: Board, black_tomove: bool, mcts_num: usize) -> SidedMagister {
+        self.current_num = mcts_num;
+        SidedMagister::new(state, black_tomove, mcts_num, self.options.threads, self.options.minimum, self.options.depth)
+    }
+
+    /// simulations for the limits of the `go` command
+    fn search_simulations(&self, movetime: Option<time::Duration>, nodes: Option<usize>) -> usize {
+        match (nodes, movetime) {
+            (Some(nodes), _) => nodes.max(1),
+            (None, Some(movetime)) => fit_simulations(self.options.simulations, self.simulation_time, movetime),
+            (None, None) => self.options.simulations,
+        }
+    }
+
+    /// lets the AI search the position in its own thread, so `isready` is answered meanwhile
+    fn go(&mut self, movetime: Option<time::Duration>, nodes: Option<usize>) -> Result<(), String> {
+        let (board, black_tomove) = self.position.take().ok_or("go needs a position first")?;
+        let mcts_num = self.search_simulations(movetime, nodes);
+        let mut magister = match self.magister.take() {
+            Some(magister) if !needs_new_instance(self.current_num, mcts_num) => magister,
+            Some(mut magister) => {
+                magister.stop_execution();
+                self.create_magister(board, black_tomove, mcts_num)
+            }
+            None => self.create_magister(board, black_tomove, mcts_num),
+        };
+        self.search = Some((board, black_tomove, self.current_num));
+        let results = self.results.clone();
+        thread::spawn(move || {
+            let started = time::Instant::now();
+            let chosen_state = magister.own_move();
+            let _ = results.send((magister, chosen_state, started.elapsed()));
+        });
+        Ok(())
+    }
+
+    fn search_finished(&mut self, (magister, chosen_state, elapsed): SearchResult) {
+        let Some((board, black_tomove, mcts_num)) = self.search.take() else {
+            return;
+        };
+        self.magister = Some(magister);
+        self.simulation_time = Some(elapsed.as_secs_f64() / mcts_num as f64);
+        let game = MoveHistory::new_game(board, black_tomove);
+        let notation = state_to_notation(&game, chosen_state).unwrap_or_else(|| "none".to_string());
+        println!("info nodes {} time {} pv {}", mcts_num, elapsed.as_millis(), notation);
+        println!("bestmove {}", notation);
+        let state = if black_tomove {AbaloneGame::rotate_board(chosen_state)} else {chosen_state};
+        self.last_move = Some((state, black_tomove));
+    }
+}
+
+/// reads the commands in their own thread, so they can be received during a search
+fn read_commands() -> Receiver<String> {
+    let (line_sender, lines) = unbounded();
+    thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                return;
+            };
+            if line_sender.send(line).is_err() {
+                return;
+            }
+        }
+    });
+    lines
+}
+
+fn main() {
+    if std::env::args().len() > 1 {
+        println!("{}", USAGE);
+        return;
+    }
+    let commands = read_commands();
+    let (results, search_results) = unbounded();
+    let mut engine = MagisterEngine::new(results);
+    // commands that have to wait for the running search and when they arrived
+    let mut deferred: VecDeque<(EngineCommand, time::Instant)> = VecDeque::new();
+    loop {
+        select! {
+            recv(commands) -> line => {
+                // the GUI is gone
+                let Ok(line) = line else {
+                    break;
+                };
+                let Some(command) = EngineCommand::parse(&line) else {
+                    continue;
+                };
+                match command {
+                    EngineCommand::Quit => break,
+                    EngineCommand::IsReady | EngineCommand::Stop => {}
+                    _ if engine.is_searching() => {
+                        deferred.push_back((command, time::Instant::now()));
+                        continue;
+                    }
+                    _ => {}
+                }
+                if let Err(msg) = engine.handle(command) {
+                    eprintln!("{}", msg);
+                }
+            }
+            recv(search_results) -> result => {
+                if let Ok(result) = result {
+                    engine.search_finished(result);
+                }
+                while !engine.is_searching() {
+                    let Some((mut command, arrived)) = deferred.pop_front() else {
+                        break;
+                    };
+                    // the time spent waiting counts against the move time
+                    if let EngineCommand::Go { movetime: Some(movetime), .. } = &mut command {
+                        *movetime = movetime.saturating_sub(arrived.elapsed());
+                    }
+                    if let Err(msg) = engine.handle(command) {
+                        eprintln!("{}", msg);
+                    }
+                }
+            }
+        }
+    }
+    if let Some(mut magister) = engine.magister.take() {
+        magister.stop_execution();
+    }
+}
@@ -0,0 +1,156 @@
+use rustai_abalone::game::{AbaloneGame, Board, BELGIAN_DAISY, CLASSIC, GERMAN_DAISY};
+use rustai_abalone::player::MagisterLudi;
+use play_abalone::player_setting::PlayerSetting;
+use play_abalone::position::format_position;
+
+const USAGE: &str = "usage: match_runner [--games N] [--max-plies N] [--first NUM,PARALLEL,MINIMUM,DEPTH] [--second NUM,PARALLEL,MINIMUM,DEPTH]
+
+Plays N games between two Magister Ludi AIs from every starting position (Belgian Daisy, German Daisy, Classic).
+The colors alternate, so N should be even. The results are given from the view of the first AI.
+A game that reaches the maximum number of plies (default 400) is a draw.";
+
+/// games of AIs that only push marbles back and forth are ended as a draw
+const MAX_PLIES: usize = 400;
+
+/// wins, draws and losses of the first AI
+#[derive(Default, Clone, Copy)]
+struct MatchResult {
+    wins: usize,
+    draws: usize,
+    losses: usize,
+}
+
+impl MatchResult {
+    fn add(&mut self, other: MatchResult) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+    }
+
+    fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    /// average score (win = 1, draw = 0.5) and the half width of its 95% confidence interval
+    fn score_interval(&self) -> (f64, f64) {
+        let games = self.games() as f64;
+        if games == 0.0 {
+            return (0.0, 0.0);
+        }
+        let score = (self.wins as f64 + 0.5 * self.draws as f64) / games;
+        let deviation = (self.wins as f64 * (1.0 - score).powi(2)
+            + self.draws as f64 * (0.5 - score).powi(2)
+            + self.losses as f64 * score.powi(2)) / games;
+        (score, 1.96 * (deviation / games).sqrt())
+    }
+
+    fn print(&self, name: &str) {
+        let (score, half_width) = self.score_interval();
+        println!(
+            "{:<14} +{} ={} -{}  score {:.1}% ± {:.1}% (95% CI)",
+            name, self.wins, self.draws, self.losses, 100.0 * score, 100.0 * half_width
+        );
+    }
+}
+
+fn parse_setting(text: &str) -> Result<PlayerSetting, String> {
+    match PlayerSetting::parse(text)? {
+        setting @ PlayerSetting::MagisterLudiAI { .. } => Ok(setting),
+        _ => Err("only Magister Ludi AIs can play in the match runner".to_string()),
+    }
+}
+
+fn create_player(setting: &PlayerSetting, state: Board) -> MagisterLudi {
+    match setting {
+        PlayerSetting::MagisterLudiAI { mcts_num, mcts_parallel, mcts_minimum, mcts_depth, .. } => {
+            MagisterLudi::new(state, None, *mcts_num, *mcts_parallel, *mcts_minimum, *mcts_depth)
+        }
+        _ => panic!("only Magister Ludi AIs can play in the match runner"),
+    }
+}
+
+/// plays one game and returns the result of the game (-1 blue won, 0 draw, 1 yellow won) and the final position
+fn play_game(start: Board, black_setting: &PlayerSetting, white_setting: &PlayerSetting, max_plies: usize) -> (i8, String) {
+    let mut game = AbaloneGame::new(start);
+    let mut black_magister = create_player(black_setting, game.get_state());
+    let mut white_magister = create_player(white_setting, game.get_state());
+    for ply in 1.. {
+        // the same protocol as the worker thread of the GUI
+        let is_blacksmove = game.get_black_tomove();
+        let (current_player, waiting_player) = if is_blacksmove {
+            (&mut black_magister, &mut white_magister)
+        } else {
+            (&mut white_magister, &mut black_magister)
+        };
+        let mut chosen_state = current_player.own_move(false);
+        if is_blacksmove {
+            chosen_state = AbaloneGame::rotate_board(chosen_state);
+        }
+        game.update_state(chosen_state);
+        if game.get_game_ended() || ply >= max_plies {
+            current_player.stop_execution();
+            waiting_player.stop_execution();
+            break;
+        }
+        waiting_player.external_move(chosen_state, true);
+    }
+    // a game stopped after the maximum number of plies is still running and counts as a draw
+    let result = match game.get_game_result() {
+        -1 => -1,
+        1 => 1,
+        _ => 0,
+    };
+    (result, format_position(game.get_state(), game.get_black_tomove()))
+}
+
+fn main() {
+    let mut num_games: usize = 2;
+    let mut max_plies = MAX_PLIES;
+    let mut first = PlayerSetting::default_ai();
+    let mut second = first.clone();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut arg_iter = args.iter();
+    while let Some(arg) = arg_iter.next() {
+        let parsed = match (arg.as_str(), arg_iter.next()) {
+            ("--games", Some(value)) => value.parse::<usize>().map(|n| num_games = n).map_err(|err| err.to_string()),
+            ("--max-plies", Some(value)) => value.parse::<usize>().map(|n| max_plies = n.max(1)).map_err(|err| err.to_string()),
+            ("--first", Some(value)) => parse_setting(value).map(|setting| first = setting),
+            ("--second", Some(value)) => parse_setting(value).map(|setting| second = setting),
+            _ => Err(format!("unexpected argument '{}'", arg)),
+        };
+        if let Err(msg) = parsed {
+            eprintln!("{}\n\n{}", msg, USAGE);
+            std::process::exit(2);
+        }
+    }
+
+    let starting_positions = [("Belgian Daisy", BELGIAN_DAISY), ("German Daisy", GERMAN_DAISY), ("Classic", CLASSIC)];
+    let mut total = MatchResult::default();
+    for (name, start) in starting_positions {
+        let mut result = MatchResult::default();
+        for game_num in 0..num_games {
+            // the first AI plays blue in every even game
+            let first_is_black = game_num % 2 == 0;
+            let (game_result, final_position) = if first_is_black {
+                play_game(start, &first, &second, max_plies)
+            } else {
+                let (game_result, final_position) = play_game(start, &second, &first, max_plies);
+                (-game_result, final_position)
+            };
+            match game_result {
+                -1 => result.wins += 1,
+                1 => result.losses += 1,
+                _ => result.draws += 1,
+            }
+            println!("{} game {}: {} ({})", name, game_num + 1, match game_result {
+                -1 => "first AI won",
+                1 => "second AI won",
+                _ => "draw",
+            }, final_position);
+        }
+        result.print(name);
+        total.add(result);
+    }
+    total.print("Total");
+}
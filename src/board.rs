@@ -0,0 +1,500 @@
+use std::collections::HashMap;
+use rustai_abalone::game::{AbaloneGame, Board, Coord, MarbleMove};
+use eframe::egui;
+use egui::{Sense, Shape, Vec2, Align2};
+use epaint::{pos2, CircleShape, Color32, Pos2, Stroke};
+use epaint::emath::TSTransform;
+
+use crate::coloring::AbaloneColors;
+use crate::constants::{BASE_WIDTH, BASE_HEIGHT, MARBLE_SIZE, DIST_SIZE, COL_OFFSETS, CONTROLS_HEIGHT, BOARD_HEIGHT};
+use crate::labels::AbaloneLabels;
+use crate::positions::AbalonePositions;
+use crate::side::SidedGame;
+
+fn load_image_from_path(path: &std::path::Path) -> Result<egui::ColorImage, image::ImageError> {
+    let image = image::ImageReader::open(path)?.decode()?;
+    Ok(to_color_image(image))
+}
+
+fn load_image_from_bytes(bytes: &[u8]) -> Result<egui::ColorImage, image::ImageError> {
+    let image = image::load_from_memory(bytes)?;
+    Ok(to_color_image(image))
+}
+
+fn to_color_image(image: image::DynamicImage) -> egui::ColorImage {
+    let size = [image.width() as _, image.height() as _];
+    let image_buffer = image.to_rgba8();
+    let pixels = image_buffer.as_flat_samples();
+    egui::ColorImage::from_rgba_unmultiplied(
+        size,
+        pixels.as_slice(),
+    )
+}
+
+/// texture of a marble or a plain circle if the image could not be loaded
+pub enum MarbleImage {
+    Texture(egui::TextureHandle),
+    Vector { fill: Color32, stroke: Color32 },
+}
+
+impl MarbleImage {
+    /// loads the image from the skin directory if it contains the file, otherwise the embedded image is used
+    /// the marble is left unchanged if no image can be decoded
+    fn load(&mut self, ctx: &egui::Context, name: &str, file_name: &str, embedded: &[u8], skin_dir: Option<&std::path::Path>) {
+        let skin_image = skin_dir
+            .map(|dir| dir.join(file_name))
+            .filter(|path| path.is_file())
+            .and_then(|path| load_image_from_path(path.as_path()).ok());
+        if let Ok(image) = skin_image.map_or_else(|| load_image_from_bytes(embedded), Ok) {
+            *self = MarbleImage::Texture(ctx.load_texture(name, image, egui::TextureOptions::default()));
+        }
+    }
+
+    /// paints the marble into the given rect
+    pub fn paint(&self, painter: &egui::Painter, rect: egui::Rect, uv: egui::Rect) {
+        match self {
+            MarbleImage::Texture(texture) => {
+                painter.image(egui::TextureId::from(texture), rect, uv, Color32::WHITE);
+            }
+            MarbleImage::Vector { fill, stroke } => {
+                painter.circle(rect.center(), 0.45 * rect.width(), *fill, Stroke::new(0.05 * rect.width(), *stroke));
+            }
+        }
+    }
+
+    /// places a clickable marble into the given rect
+    pub fn button(&self, ui: &mut egui::Ui, rect: egui::Rect) -> egui::Response {
+        match self {
+            MarbleImage::Texture(texture) => ui.put(
+                rect,
+                egui::ImageButton::new((egui::TextureId::from(texture), rect.size())).frame(false)
+            ),
+            MarbleImage::Vector { fill, stroke } => ui.put(
+                rect,
+                egui::Button::new("")
+                    .fill(*fill)
+                    .stroke(Stroke::new(0.05 * rect.width(), *stroke))
+                    .rounding(0.5 * rect.width())
+                    .min_size(rect.size())
+            ),
+        }
+    }
+}
+
+/// board panel that paints an abalone game and lets a human player choose moves
+pub struct AbaloneBoard {
+    /// skull image for dead marbles
+    pub skull_marble: MarbleImage,
+    /// black marble image
+    pub black_marble: MarbleImage,
+    /// white marble image
+    pub white_marble: MarbleImage,
+    /// marble image for inactive button
+    pub nomove_marble: MarbleImage,
+    /// all positions for gui
+    pub pos: AbalonePositions,
+    /// maps the possble moves to a follow-up state
+    pub move_states: HashMap<MarbleMove, Board>,
+    /// all colors come here:
+    pub colors: AbaloneColors,
+    /// all egui stuff down here
+    pub uv: egui::Rect,
+    /// screen area of the board in the last frame
+    pub board_rect: egui::Rect,
+}
+
+impl AbaloneBoard {
+    const ROW_LENGTHS: [f32; 9] = [5.0, 6.0, 7.0, 8.0, 9.0, 8.0, 7.0, 6.0, 5.0];
+    const COL_VALUES: [f32; 9] = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0];
+
+    /// board with the images compiled into the binary
+    pub fn new(ctx: &egui::Context) -> Self {
+        let colors = AbaloneColors::default();
+        let mut board = Self {
+            skull_marble: MarbleImage::Vector { fill: Color32::from_gray(20), stroke: Color32::LIGHT_RED },
+            black_marble: MarbleImage::Vector { fill: colors.color_black_stroke, stroke: Color32::BLACK },
+            white_marble: MarbleImage::Vector { fill: colors.color_white_stroke, stroke: Color32::BLACK },
+            nomove_marble: MarbleImage::Vector { fill: Color32::GRAY, stroke: Color32::DARK_GRAY },
+            pos: AbalonePositions::default(),
+            move_states: HashMap::with_capacity(6),
+            colors,
+            uv: egui::Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0)),
+            board_rect: egui::Rect::NOTHING,
+        };
+        board.load_skin(ctx, None);
+        board
+    }
+
+    /// loads the images of the skin directory, missing files are replaced by the compiled in images
+    /// images that cannot be decoded keep being painted as plain circles
+    pub fn load_skin(&mut self, ctx: &egui::Context, skin_dir: Option<&std::path::Path>) {
+        let images = [
+            (&mut self.skull_marble, "skull", "skull.png", &include_bytes!("images/skull.png")[..]),
+            (&mut self.black_marble, "black", "marble_blue.png", &include_bytes!("images/marble_blue.png")[..]),
+            (&mut self.white_marble, "white", "marble_yellow.png", &include_bytes!("images/marble_yellow.png")[..]),
+            (&mut self.nomove_marble, "nomove", "marble_empty.png", &include_bytes!("images/marble_empty.png")[..]),
+        ];
+        for (marble, name, file_name, embedded) in images {
+            marble.load(ctx, name, file_name, embedded, skin_dir);
+        }
+    }
+
+    pub fn coord_to_center(coord: Coord, geometry: TSTransform) -> Pos2 {
+        // note that in Coord x is for rows and y for columns
+        // for the screen it is the other way round
+        let x = BASE_WIDTH - DIST_SIZE * (
+            0.5 * (Self::ROW_LENGTHS[coord.x-1] - 5.0) - Self::COL_VALUES[coord.y-COL_OFFSETS[coord.x-1]]
+        );
+        let y = BASE_HEIGHT + MARBLE_SIZE * Self::COL_VALUES[coord.x-1];
+        geometry * Pos2{ x, y }
+    }
+
+    /// rect of the given size in the board layout around a screen position
+    fn scaled_rect(&self, center: Pos2, size: f32) -> egui::Rect {
+        egui::Rect::from_center_size(center, Vec2::splat(size * self.pos.geometry.scaling))
+    }
+
+    /// allocates the space for the board and adjusts all positions to it
+    pub fn allocate_board(&mut self, ui: &mut egui::Ui) -> egui::Painter {
+        let available = ui.available_size();
+        let board_height = (available.y - CONTROLS_HEIGHT).max(0.25 * BOARD_HEIGHT);
+        let (response, painter) = ui.allocate_painter(Vec2::new(available.x, board_height), Sense::hover());
+        self.board_rect = response.rect;
+        self.pos.set_geometry(AbalonePositions::fit_geometry(response.rect));
+        painter
+    }
+
+    /// area below the board for buttons and settings
+    pub fn controls_rect(&self, ui: &egui::Ui) -> egui::Rect {
+        egui::Rect::from_min_max(
+            self.board_rect.left_bottom(),
+            pos2(self.board_rect.right(), ui.max_rect().bottom().max(self.board_rect.bottom() + CONTROLS_HEIGHT))
+        )
+    }
+
+    /// font of the labels scaled to the board
+    pub fn scaled_font(&self, glabels: &AbaloneLabels) -> egui::FontId {
+        egui::FontId::new(glabels.font.size * self.pos.geometry.scaling, glabels.font.family.clone())
+    }
+
+    /// deselects all marbles and removes the move buttons
+    pub fn clear_selection(&mut self) {
+        self.move_states.clear();
+        self.pos.color_selection.clear();
+        self.pos.circle_selection.clear();
+        self.pos.hint_move = None;
+    }
+
+    /// adds circles around the marbles moved by the last player
+    pub fn highlight_move(&mut self, move_circles: impl IntoIterator<Item = Coord>, was_black_last: bool) {
+        let color_fill = if was_black_last {&self.colors.color_black_fill} else {&self.colors.color_white_fill};
+        let color_stroke = if was_black_last {&self.colors.move_black_stroke} else {&self.colors.move_white_stroke};
+        let geometry = self.pos.geometry;
+        for coord in move_circles {
+            self.pos.circle_move_empty.push(
+                Shape::Circle(CircleShape {
+                    center: Self::coord_to_center(coord, geometry),
+                    radius: 32.0 * geometry.scaling,
+                    fill: *color_fill,
+                    stroke: Stroke::new(2.0 * geometry.scaling, *color_stroke),
+                })
+            );
+        }
+    }
+
+    pub fn game_painter_vectors(&mut self, game: &SidedGame, glabels: &mut AbaloneLabels) {
+        let (blacks, whites, empties) = game.get_coords_by_type();
+        let (black_loss, white_loss) = game.get_black_white_loss();
+        let geometry = self.pos.geometry;
+
+        // handle board positions
+        self.pos.black_pos = blacks.iter().map(|coord| (*coord, Self::coord_to_center(*coord, geometry))).collect();
+        self.pos.white_pos = whites.iter().map(|coord| (*coord, Self::coord_to_center(*coord, geometry))).collect();
+        self.pos.circle_move_empty.clear();
+        for coord in empties {
+            self.pos.circle_move_empty.push(
+                Shape::Circle(CircleShape {
+                    center: Self::coord_to_center(coord, geometry),
+                    radius: 10.0 * geometry.scaling,
+                    fill: self.colors.color_empty_fill,
+                    stroke: Stroke::new(5.0 * geometry.scaling, self.colors.color_empty_stroke),
+                })
+            );
+        }
+
+        // handle dead zone positions
+        self.pos.skull_pos.clear();
+        let old_blackloss = self.pos.black_died.len();
+        self.pos.black_died.clear();
+        let old_whiteloss = self.pos.white_died.len();
+        self.pos.white_died.clear();
+        self.fill_deadzone_vectors(true, usize::from(black_loss), old_blackloss);
+        self.fill_deadzone_vectors(false, usize::from(white_loss), old_whiteloss);
+
+        // handle game message and color
+        self.set_game_message(game.get_game_result(), game.get_black_tomove(), glabels);
+    }
+
+    /// sets the game message and its color for the game result (-1 blue won, 0 draw, 1 yellow won, anything else: still running)
+    pub fn set_game_message(&mut self, result: i8, is_blacksmove: bool, glabels: &mut AbaloneLabels) {
+        self.colors.text_color = match result {
+            -1 => self.colors.color_black_stroke.clone(),
+            0 => Color32::WHITE,
+            1 => self.colors.color_white_stroke.clone(),
+            _ => if is_blacksmove {self.colors.color_black_stroke.clone()} else {self.colors.color_white_stroke.clone()},
+        };
+        glabels.game_message = match result {
+            -1 => format!("'{}' won the game!", glabels.black_name),
+            0 => "The game ended in a draw".to_string(),
+            1 => format!("'{}' won the game!", glabels.white_name),
+            _ => if is_blacksmove {
+                format!("'{}' has to make a move", glabels.black_name)
+            } else {
+                format!("'{}' has to make a move", glabels.white_name)
+            },
+        }
+    }
+
+    /// sets the game message for a game lost on time
+    pub fn set_time_loss_message(&mut self, black_lost: bool, glabels: &mut AbaloneLabels) {
+        let (winner, color) = if black_lost {
+            (&glabels.white_name, self.colors.color_white_stroke)
+        } else {
+            (&glabels.black_name, self.colors.color_black_stroke)
+        };
+        glabels.game_message = format!("'{}' won on time!", winner);
+        self.colors.text_color = color;
+    }
+
+    fn fill_deadzone_vectors(&mut self, is_black: bool, loss: usize, old_loss: usize) {
+        let geometry = self.pos.geometry;
+        let (deadzone, dead_marbles, color_stroke, color_fill) = if is_black {
+            (&mut self.pos.black_deads, &mut self.pos.black_died, &self.colors.move_white_stroke, &self.colors.color_white_fill)
+        } else {
+            (&mut self.pos.white_deads, &mut self.pos.white_died, &self.colors.move_black_stroke, &self.colors.color_black_fill)
+        };
+        for (num, position) in deadzone.iter().enumerate() {
+            if num < loss {
+                dead_marbles.push(position.clone());
+                self.pos.skull_pos.push(position.clone());
+                if num == old_loss {
+                    self.pos.circle_move_empty.push(
+                        Shape::Circle(CircleShape {
+                            center: *position,
+                            radius: 32.0 * geometry.scaling,
+                            fill: *color_fill,
+                            stroke: Stroke::new(2.0 * geometry.scaling, *color_stroke),
+                        })
+                    );
+                }
+            } else {
+                self.pos.circle_move_empty.push(
+                    Shape::Circle(CircleShape {
+                        center: *position,
+                        radius: 10.0 * geometry.scaling,
+                        fill: self.colors.color_empty_fill,
+                        stroke: Stroke::new(5.0 * geometry.scaling, self.colors.color_empty_stroke),
+                    })
+                );
+            }
+        }
+    }
+
+    /// shows a board without a running game, e.g. a starting position
+    pub fn board_painter_vectors(&mut self, board: Board) {
+        let (blacks, whites, empties) = AbaloneGame::coords_by_type(board);
+        let geometry = self.pos.geometry;
+
+        // handle board positions
+        self.pos.black_pos = blacks.iter().map(|coord| (*coord, Self::coord_to_center(*coord, geometry))).collect();
+        self.pos.white_pos = whites.iter().map(|coord| (*coord, Self::coord_to_center(*coord, geometry))).collect();
+        self.pos.circle_move_empty.clear();
+        for coord in empties {
+            self.pos.circle_move_empty.push(
+                Shape::Circle(CircleShape {
+                    center: Self::coord_to_center(coord, geometry),
+                    radius: 10.0 * geometry.scaling,
+                    fill: self.colors.color_empty_fill,
+                    stroke: Stroke::new(5.0 * geometry.scaling, self.colors.color_empty_stroke),
+                })
+            );
+        }
+    }
+
+    pub fn colorize_selection(&mut self, game: &SidedGame, selec_coord: Coord) {
+        if self.pos.color_selection.contains(&selec_coord) {
+            // if the marble was already selected, de-select it
+            self.pos.color_selection.remove(&selec_coord);
+        } else if self.pos.allowed_selection.contains(&selec_coord) {
+            // first adjust selection, there will be new selections no matter what happend before
+            self.pos.allowed_selection.clear();
+            if self.pos.color_selection.len() == 1 {
+                // only special case, if the allowed_selection was empty, the condition would not be satisfied in the first place
+                let init_coord = self.pos.color_selection.iter().next().unwrap();
+                let move_diff = selec_coord - *init_coord;
+                self.pos.allowed_selection.insert(selec_coord + move_diff);
+                self.pos.allowed_selection.insert(*init_coord - move_diff);
+            }
+
+            // if the marble can be added to a line, add it
+            self.pos.color_selection.insert(selec_coord);
+
+        // adjust allowed selection
+        } else {
+            // otherwise this will be a new selection
+            self.pos.color_selection.clear();
+            self.pos.color_selection.insert(selec_coord);
+
+            // now all positions around the selected one are allowed
+            self.pos.allowed_selection.clear();
+            for marb_move in AbaloneGame::get_game_moves() {
+                self.pos.allowed_selection.insert(selec_coord + marb_move);
+            }
+        }
+
+        self.pos.hint_move = None;
+        self.update_selection(game);
+    }
+
+    /// selects the marbles of a suggested move and marks its move button
+    pub fn show_hint(&mut self, game: &SidedGame, coords: &[Coord], marble_move: MarbleMove) {
+        self.pos.color_selection = coords.iter().copied().collect();
+        self.pos.allowed_selection.clear();
+        self.pos.hint_move = Some(marble_move);
+        self.update_selection(game);
+    }
+
+    /// draws circles around the selected marbles and calculates the possible moves
+    fn update_selection(&mut self, game: &SidedGame) {
+        // draw new circles
+        let is_blacksmove = game.get_black_tomove();
+        let color_fill = if is_blacksmove {&self.colors.color_black_fill} else {&self.colors.color_white_fill};
+        let color_stroke = if is_blacksmove {&self.colors.color_black_stroke} else {&self.colors.color_white_stroke};
+        let geometry = self.pos.geometry;
+        self.pos.circle_selection = self.pos.color_selection.iter().map(|coord| {
+            Shape::Circle(CircleShape {
+                center: Self::coord_to_center(*coord, geometry),
+                radius: 32.0 * geometry.scaling,
+                fill: *color_fill,
+                stroke: Stroke::new(2.0 * geometry.scaling, *color_stroke),
+            })
+        }).collect();
+
+        // update buttons
+        let start_coords = self.pos.color_selection.iter().map(|c| c.clone()).collect();
+        self.move_states = game.calc_coord_moves(start_coords);
+    }
+
+    /// paints the circles, the game message and the deadzones of the current game
+    pub fn paint_game_info(&self, painter: &egui::Painter, glabels: &AbaloneLabels) {
+        // paint circles first as the marbles will be printed above
+        painter.extend(self.pos.circle_move_empty.clone());
+        painter.extend(self.pos.circle_selection.clone());
+
+        // print game message
+        painter.text(
+            self.pos.geometry * pos2(BASE_WIDTH+200.0, BASE_HEIGHT),
+            Align2::CENTER_CENTER,
+            glabels.game_message.clone(),
+            self.scaled_font(glabels),
+            self.colors.text_color
+        );
+
+        // print clocks next to the deadzones
+        for (clock, position, color) in [
+            (&glabels.black_clock, pos2(BASE_WIDTH-265.0, BASE_HEIGHT+4.0*MARBLE_SIZE), self.colors.color_black_stroke),
+            (&glabels.white_clock, pos2(BASE_WIDTH-265.0, BASE_HEIGHT+6.0*MARBLE_SIZE), self.colors.color_white_stroke),
+        ] {
+            if !clock.is_empty() {
+                painter.text(self.pos.geometry * position, Align2::CENTER_CENTER, clock, self.scaled_font(glabels), color);
+            }
+        }
+
+        // paint deadzones afterwards
+        for (marble, dead_pos) in [(&self.black_marble, &self.pos.black_died), (&self.white_marble, &self.pos.white_died)] {
+            for position in dead_pos.iter() {
+                marble.paint(painter, self.scaled_rect(*position, MARBLE_SIZE), self.uv);
+                self.skull_marble.paint(painter, self.scaled_rect(*position, 42.0), self.uv);
+            }
+        }
+    }
+
+    /// paints the marbles of both players as images
+    pub fn paint_marbles(&self, painter: &egui::Painter) {
+        for (marble, marble_pos) in [(&self.black_marble, &self.pos.black_pos), (&self.white_marble, &self.pos.white_pos)] {
+            for (_, position) in marble_pos.iter() {
+                marble.paint(painter, self.scaled_rect(*position, MARBLE_SIZE), self.uv);
+            }
+        }
+    }
+
+    /// paints the game, nothing can be clicked
+    pub fn show_static(&mut self, ui: &mut egui::Ui, glabels: &AbaloneLabels) {
+        let painter = self.allocate_board(ui);
+        self.paint_game_info(&painter, glabels);
+        self.paint_marbles(&painter);
+    }
+
+    /// paints the game with the marbles of the player to move as buttons if `is_active` is set
+    /// returns the follow-up state if a move button was clicked
+    pub fn show(&mut self, ui: &mut egui::Ui, game: &SidedGame, is_active: bool, glabels: &AbaloneLabels) -> Option<Board> {
+        let painter = self.allocate_board(ui);
+        self.paint_game_info(&painter, glabels);
+
+        let is_blacksmove = game.get_black_tomove();
+
+        // paint clickable and unclickable marbles for active and waiting player respectively
+        let (active_marble, waiting_marble, active_pos, waiting_pos) = if is_blacksmove {
+            (&self.black_marble, &self.white_marble, &self.pos.black_pos, &self.pos.white_pos)
+        } else {
+            (&self.white_marble, &self.black_marble, &self.pos.white_pos, &self.pos.black_pos)
+        };
+        // easy part: just paint waiting player's marbles
+        for (_, position) in waiting_pos.iter() {
+            waiting_marble.paint(&painter, self.scaled_rect(*position, MARBLE_SIZE), self.uv);
+        }
+        // paint clickable images for color selection
+        let mut selected_next_state: Option<Board> = None;
+        let mut selected_coord: Option<Coord> = None;
+        if is_active {
+            // place buttons if the current player is human
+            for (coord, position) in active_pos.iter() {
+                if active_marble.button(ui, self.scaled_rect(*position, MARBLE_SIZE)).clicked() {
+                   selected_coord = Some(*coord);
+                };
+            }
+        } else {
+            // place images if the current player is AI
+            for (_, position) in active_pos.iter() {
+                active_marble.paint(&painter, self.scaled_rect(*position, MARBLE_SIZE), self.uv);
+            }
+        }
+
+        // place buttons
+        for (marb_move, position, direction) in self.pos.move_pos.iter() {
+            match self.move_states.get(marb_move) {
+                Some(next_state) => {
+                    if active_marble.button(ui, self.scaled_rect(*position, MARBLE_SIZE)).clicked() {
+                       selected_next_state = Some(*next_state);
+                    };
+                    painter.arrow(*position, *direction, Stroke{width: 3.0 * self.pos.geometry.scaling, color: Color32::WHITE});
+                    if self.pos.hint_move == Some(*marb_move) {
+                        painter.circle_stroke(
+                            *position, 0.5 * (MARBLE_SIZE + 6.0) * self.pos.geometry.scaling,
+                            Stroke::new(3.0 * self.pos.geometry.scaling, Color32::WHITE));
+                    }
+                }
+                _ => {
+                    self.nomove_marble.paint(&painter, self.scaled_rect(*position, MARBLE_SIZE), self.uv);
+                }
+            }
+        }
+
+        // handle clicks
+        match selected_coord {
+            Some(c) => self.colorize_selection(game, c),
+            _ => {}
+        };
+        selected_next_state
+    }
+}
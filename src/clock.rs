@@ -0,0 +1,248 @@
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+
+/// thinking time of every player
+#[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Default)]
+pub enum TimeControl {
+    #[default]
+    Unlimited,
+    /// the whole game has to be played within the given time
+    SuddenDeath { minutes: u64 },
+    /// after every move the increment is added to the clock
+    Fischer { minutes: u64, increment: u64 },
+    /// after the main time every move has to be made within one period, an exceeded period is lost
+    ByoYomi { minutes: u64, periods: u32, period: u64 },
+}
+
+impl TimeControl {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimeControl::Unlimited => "Unlimited",
+            TimeControl::SuddenDeath { .. } => "Sudden death",
+            TimeControl::Fischer { .. } => "Fischer",
+            TimeControl::ByoYomi { .. } => "Byo-yomi",
+        }
+    }
+}
+
+/// remaining time of one player
+#[derive(Clone, Copy)]
+struct PlayerTime {
+    main: Duration,
+    periods: u32,
+}
+
+/// clocks of both players, only the clock of the player to move is running
+#[derive(Clone)]
+pub struct GameClock {
+    pub time_control: TimeControl,
+    black: PlayerTime,
+    white: PlayerTime,
+    /// player whose clock is running and since when
+    running: Option<(bool, Instant)>,
+}
+
+impl GameClock {
+    pub fn new(time_control: TimeControl) -> Self {
+        let player_time = match time_control {
+            TimeControl::Unlimited => PlayerTime { main: Duration::ZERO, periods: 0 },
+            TimeControl::SuddenDeath { minutes } | TimeControl::Fischer { minutes, increment: _ } => {
+                PlayerTime { main: Duration::from_secs(60 * minutes), periods: 0 }
+            }
+            TimeControl::ByoYomi { minutes, periods, period: _ } => {
+                PlayerTime { main: Duration::from_secs(60 * minutes), periods }
+            }
+        };
+        Self {
+            time_control,
+            black: player_time,
+            white: player_time,
+            running: None,
+        }
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.time_control == TimeControl::Unlimited
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// starts the clock of the given player, a running clock is stopped without increment
+    pub fn start(&mut self, for_black: bool) {
+        self.stop();
+        self.running = Some((for_black, Instant::now()));
+    }
+
+    /// stops the running clock without increment
+    pub fn stop(&mut self) {
+        if let Some((running_black, since)) = self.running.take() {
+            // the used part of a byo-yomi period is forgotten
+            let (player_time, _) = self.player_time(running_black, since.elapsed());
+            *self.player_time_mut(running_black) = player_time;
+        }
+    }
+
+    /// stops the clock of the player who just moved, adds the increment and starts the clock of the other player
+    pub fn press(&mut self, black_moved: bool) {
+        self.stop();
+        if let TimeControl::Fischer { minutes: _, increment } = self.time_control {
+            self.player_time_mut(black_moved).main += Duration::from_secs(increment);
+        }
+        self.running = Some((!black_moved, Instant::now()));
+    }
+
+    /// the player ran out of time
+    pub fn flag_fallen(&self, for_black: bool) -> bool {
+        if self.is_unlimited() {
+            return false;
+        }
+        let (player_time, _) = self.player_time(for_black, self.elapsed(for_black));
+        player_time.main.is_zero() && player_time.periods == 0
+    }
+
+    /// time that may still be used for the current move without losing the game or a period
+    pub fn remaining(&self, for_black: bool) -> Duration {
+        let (player_time, period_used) = self.player_time(for_black, self.elapsed(for_black));
+        match self.time_control {
+            TimeControl::ByoYomi { minutes: _, periods: _, period } if player_time.periods > 0 => {
+                player_time.main + Duration::from_secs(period) - period_used
+            }
+            _ => player_time.main,
+        }
+    }
+
+    /// thinking time an AI should use for its next move, `None` if the time is unlimited
+    pub fn move_budget(&self, for_black: bool) -> Option<Duration> {
+        let (player_time, _) = self.player_time(for_black, self.elapsed(for_black));
+        match self.time_control {
+            TimeControl::Unlimited => None,
+            // assume that there are still 30 moves to play
+            TimeControl::SuddenDeath { minutes: _ } => Some(player_time.main / 30),
+            TimeControl::Fischer { minutes: _, increment } => {
+                Some((player_time.main / 30 + Duration::from_secs(increment) * 4 / 5).min(player_time.main / 2))
+            }
+            TimeControl::ByoYomi { minutes: _, periods: _, period } => {
+                let period_budget = Duration::from_secs(period) * 4 / 5;
+                if player_time.main.is_zero() {
+                    Some(period_budget)
+                } else {
+                    Some((player_time.main / 30).max(period_budget))
+                }
+            }
+        }
+    }
+
+    /// remaining time as text, e.g. "4:59" or "0:25 (3)" for the last byo-yomi periods
+    pub fn display(&self, for_black: bool) -> String {
+        if self.is_unlimited() {
+            return "".to_string();
+        }
+        let (player_time, _) = self.player_time(for_black, self.elapsed(for_black));
+        let shown = if player_time.main.is_zero() {self.remaining(for_black)} else {player_time.main};
+        let seconds = shown.as_secs();
+        let text = format!("{}:{:02}", seconds / 60, seconds % 60);
+        if player_time.main.is_zero() && player_time.periods > 0 {
+            format!("{} ({})", text, player_time.periods)
+        } else {
+            text
+        }
+    }
+
+    fn elapsed(&self, for_black: bool) -> Duration {
+        match self.running {
+            Some((running_black, since)) if running_black == for_black => since.elapsed(),
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// time of the player after the given thinking time and the time used of the current byo-yomi period
+    fn player_time(&self, for_black: bool, elapsed: Duration) -> (PlayerTime, Duration) {
+        let mut player_time = if for_black {self.black} else {self.white};
+        if elapsed <= player_time.main {
+            player_time.main -= elapsed;
+            return (player_time, Duration::ZERO);
+        }
+        let mut overtime = elapsed - player_time.main;
+        player_time.main = Duration::ZERO;
+        if let TimeControl::ByoYomi { minutes: _, periods: _, period } = self.time_control {
+            // every exceeded period is lost, the next one starts right away
+            let period = Duration::from_secs(period);
+            while player_time.periods > 0 && overtime >= period {
+                overtime -= period;
+                player_time.periods -= 1;
+            }
+        }
+        (player_time, overtime)
+    }
+
+    fn player_time_mut(&mut self, for_black: bool) -> &mut PlayerTime {
+        if for_black {&mut self.black} else {&mut self.white}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// lets the clock of the player run as if it was started the given number of seconds ago
+    fn run_since(clock: &mut GameClock, for_black: bool, seconds: u64) {
+        let since = Instant::now().checked_sub(Duration::from_secs(seconds)).unwrap();
+        clock.running = Some((for_black, since));
+    }
+
+    #[test]
+    fn fischer_adds_the_increment_to_the_mover() {
+        let mut clock = GameClock::new(TimeControl::Fischer { minutes: 1, increment: 5 });
+        clock.press(true);
+        assert_eq!(clock.black.main, Duration::from_secs(65));
+        assert_eq!(clock.white.main, Duration::from_secs(60));
+        assert_eq!(clock.display(true), "1:05");
+        // the clock of the other player runs now, stopping it adds nothing
+        assert!(matches!(clock.running, Some((false, _))));
+        clock.stop();
+        assert!(clock.white.main <= Duration::from_secs(60));
+    }
+
+    #[test]
+    fn byo_yomi_periods_are_used_up() {
+        let clock = GameClock::new(TimeControl::ByoYomi { minutes: 1, periods: 3, period: 10 });
+        let (player_time, period_used) = clock.player_time(true, Duration::from_secs(60));
+        assert_eq!((player_time.main, player_time.periods, period_used), (Duration::ZERO, 3, Duration::ZERO));
+        let (player_time, period_used) = clock.player_time(true, Duration::from_secs(75));
+        assert_eq!((player_time.periods, period_used), (2, Duration::from_secs(5)));
+        let (player_time, _) = clock.player_time(true, Duration::from_secs(95));
+        assert_eq!(player_time.periods, 0);
+    }
+
+    #[test]
+    fn stopping_forgets_the_used_part_of_a_period() {
+        let mut clock = GameClock::new(TimeControl::ByoYomi { minutes: 1, periods: 2, period: 10 });
+        run_since(&mut clock, true, 75);
+        clock.stop();
+        assert_eq!((clock.black.main, clock.black.periods), (Duration::ZERO, 1));
+        assert_eq!(clock.remaining(true), Duration::from_secs(10));
+        assert_eq!(clock.display(true), "0:10 (1)");
+    }
+
+    #[test]
+    fn flag_falls_when_the_time_is_used_up() {
+        let mut clock = GameClock::new(TimeControl::SuddenDeath { minutes: 1 });
+        run_since(&mut clock, true, 30);
+        assert!(!clock.flag_fallen(true));
+        run_since(&mut clock, true, 61);
+        assert!(clock.flag_fallen(true));
+        assert!(!clock.flag_fallen(false));
+
+        let mut clock = GameClock::new(TimeControl::ByoYomi { minutes: 1, periods: 2, period: 10 });
+        run_since(&mut clock, false, 75);
+        assert!(!clock.flag_fallen(false));
+        run_since(&mut clock, false, 81);
+        assert!(clock.flag_fallen(false));
+
+        let mut clock = GameClock::new(TimeControl::Unlimited);
+        run_since(&mut clock, true, 61);
+        assert!(!clock.flag_fallen(true));
+    }
+}
@@ -2,4 +2,13 @@ pub const BASE_WIDTH: f32 = 400.0;
 pub const BASE_HEIGHT: f32 = 65.0;
 pub const MARBLE_SIZE: f32 = 60.0;
 pub const DIST_SIZE: f32 = 65.0;
-pub const VEC_LEN: f32 = 25.0;
\ No newline at end of file
+pub const VEC_LEN: f32 = 25.0;
+/// first column of every board row (rows top to bottom)
+pub const COL_OFFSETS: [usize; 9] = [5, 4, 3, 2, 1, 1, 1, 1, 1];
+/// number of fields of every board row (rows top to bottom)
+pub const ROW_SIZES: [usize; 9] = [5, 6, 7, 8, 9, 8, 7, 6, 5];
+/// size of the board layout all other sizes refer to, it is scaled to the available space
+pub const BOARD_WIDTH: f32 = 1200.0;
+pub const BOARD_HEIGHT: f32 = 700.0;
+/// space kept free below the board for buttons and settings
+pub const CONTROLS_HEIGHT: f32 = 160.0;
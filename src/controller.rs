@@ -0,0 +1,490 @@
+use rustai_abalone::game::{AbaloneGame, Board};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use eframe::egui;
+use std::thread::JoinHandle;
+
+use crate::clock::{GameClock, TimeControl};
+use crate::history::MoveHistory;
+use crate::network::{self, NetMessage, RemoteEvent, RemoteLink, SpectatorServer, PROTOCOL_VERSION, SPECTATOR_PORT};
+use crate::notation::find_move;
+use crate::player_setting::PlayerSetting;
+use crate::position::format_position;
+use crate::record::{board_from_string, board_to_string};
+use crate::side::SidedGame;
+use crate::worker::{Worker, WorkerCommand, WorkerEvent};
+
+/// news for the game window, collected from the worker and the remote player
+pub enum GameEvent {
+    /// an event of the worker, moves and errors of the remote player arrive the same way
+    Worker(WorkerEvent),
+    /// the instance of the remote player connected,
+    /// the local player may have changed the color to fit the game of the host
+    RemoteConnected { remote_is_black: bool, remote_name: String, local_name: String },
+}
+
+/// runs a game between humans and AIs, the AIs think in a worker thread
+pub struct GameController {
+    /// abalone game implementation
+    pub game: SidedGame,
+    /// performed and undone moves of the current game
+    pub history: MoveHistory,
+    pub black_ai: PlayerSetting,
+    pub white_ai: PlayerSetting,
+    /// time control for new games
+    pub time_control: TimeControl,
+    pub clock: GameClock,
+    /// set if a player lost on time, true for the blue player
+    pub lost_on_time: Option<bool>,
+    /// the clocks are stopped and no moves can be made
+    pub paused: bool,
+    /// state the requested hint is searched for
+    hint_state: Option<Board>,
+    /// changes with every new or rewound game, events of older games are dropped
+    game_id: u64,
+    /// repainted whenever the worker has news
+    repaint_ctx: Option<egui::Context>,
+    worker: Option<JoinHandle<()>>,
+    /// connection to the instance of a remote player
+    remote: Option<RemoteLink>,
+    /// spectators watching the game
+    spectators: Option<SpectatorServer>,
+    command_sender: Sender<WorkerCommand>,
+    event_receiver: Receiver<WorkerEvent>,
+}
+
+impl GameController {
+    pub fn new(board: Board) -> Self {
+        let (ctx, _) = unbounded();
+        let (_, erx) = unbounded();
+        Self {
+            game: SidedGame::new(board, true),
+            history: MoveHistory::new(board, true),
+            black_ai: PlayerSetting::Human,
+            white_ai: PlayerSetting::Human,
+            time_control: TimeControl::Unlimited,
+            clock: GameClock::new(TimeControl::Unlimited),
+            lost_on_time: None,
+            paused: false,
+            hint_state: None,
+            game_id: 0,
+            repaint_ctx: None,
+            worker: None,
+            remote: None,
+            spectators: None,
+            command_sender: ctx,
+            event_receiver: erx,
+        }
+    }
+
+    /// the context is repainted as soon as an AI made a move, a hint was found or the search statistics changed
+    pub fn set_repaint_context(&mut self, ctx: egui::Context) {
+        self.repaint_ctx = Some(ctx);
+    }
+
+    /// starts a new game from the given board with the current player settings
+    pub fn start_game(&mut self, start_state: Board, black_starts: bool) {
+        // just be sure, that the old thread is stopped
+        self.stop_worker();
+        self.game = MoveHistory::new_game(start_state, black_starts);
+        self.history = MoveHistory::new(start_state, black_starts);
+        self.paused = false;
+        self.reset_clock();
+        self.resume_clock();
+        self.worker_thread();
+    }
+
+    /// sets both clocks to the start of the time control
+    pub fn reset_clock(&mut self) {
+        self.clock = GameClock::new(self.time_control.clone());
+        self.lost_on_time = None;
+    }
+
+    /// starts the clock of the player to move, unless the game has ended or is paused
+    pub fn resume_clock(&mut self) {
+        if self.game_ended() || self.paused {
+            self.clock.stop();
+        } else if !self.clock.is_unlimited() {
+            self.clock.start(self.game.get_black_tomove());
+        }
+    }
+
+    /// ends the game if the player to move ran out of time, returns true if this happened now
+    pub fn check_flag(&mut self) -> bool {
+        let is_blacksmove = self.game.get_black_tomove();
+        if self.game_ended() || !self.clock.flag_fallen(is_blacksmove) {
+            return false;
+        }
+        self.lost_on_time = Some(is_blacksmove);
+        self.clock.stop();
+        self.stop_worker();
+        true
+    }
+
+    /// game result including losses on time (-1 blue won, 0 draw, 1 yellow won, anything else: still running)
+    pub fn game_result(&self) -> i8 {
+        match self.lost_on_time {
+            Some(true) => 1,
+            Some(false) => -1,
+            None => self.game.get_game_result(),
+        }
+    }
+
+    pub fn game_ended(&self) -> bool {
+        self.lost_on_time.is_some() || self.game.get_game_ended()
+    }
+
+    /// records the move, applies it to the game and informs the worker
+    pub fn perform_move(&mut self, next_state: Board) {
+        let black_moved = self.game.get_black_tomove();
+        self.history.push(next_state, black_moved);
+        let game_state = self.apply_move(next_state);
+        if !self.clock.is_unlimited() {
+            self.clock.press(black_moved);
+        }
+        // a move found just before the game was paused does not start the clock
+        if self.game.get_game_ended() || self.paused {
+            self.clock.stop();
+        }
+        if !self.is_remote(black_moved) {
+            if let Some(remote) = self.remote.as_mut() {
+                let ply = self.history.moves.len() - 1;
+                // a lost connection is reported by the reading thread
+                let _ = remote.send(&NetMessage::Move { ply, state: board_to_string(next_state) });
+            }
+        }
+        if let Some(spectators) = self.spectators.as_ref() {
+            spectators.push_move(self.history.moves.len() - 1, board_to_string(next_state));
+        }
+        // should this be handled?
+        let _ = self.command_sender.send(WorkerCommand::MovePlayed { state: game_state, game_ended: self.game.get_game_ended() });
+    }
+
+    /// updates the game with the chosen state and returns the state given to the game
+    pub fn apply_move(&mut self, mut next_state: Board) -> Board {
+        if self.game.get_black_tomove() {
+            next_state = AbaloneGame::rotate_board(next_state);
+        }
+        self.game.update_state(next_state);
+        next_state
+    }
+
+    /// returns the next event of the remote player or the worker, events for older games or states are dropped
+    pub fn receive_event(&mut self) -> Option<GameEvent> {
+        if let Some(event) = self.receive_remote() {
+            return Some(event);
+        }
+        loop {
+            let event = self.event_receiver.try_recv().ok()?;
+            match &event {
+                WorkerEvent::MovePlayed { game_id, state: _ } | WorkerEvent::SearchInfo { game_id, info: _ } => {
+                    if *game_id != self.game_id {
+                        continue;
+                    }
+                }
+                WorkerEvent::Hint { state, suggested: _ } => {
+                    if self.hint_state != Some(*state) {
+                        continue;
+                    }
+                    self.hint_state = None;
+                    if *state != self.game.get_state() || self.game_ended() {
+                        continue;
+                    }
+                }
+                WorkerEvent::Error(_) => {}
+            }
+            return Some(GameEvent::Worker(event));
+        }
+    }
+
+    /// handles the messages of the remote player, a broken connection is closed and reported as error
+    fn receive_remote(&mut self) -> Option<GameEvent> {
+        loop {
+            let handled = match self.remote.as_mut()?.try_recv()? {
+                RemoteEvent::Message(message) => self.remote_message(message),
+                RemoteEvent::Closed(msg) => Err(msg),
+                // the link keeps the stream of a new connection
+                RemoteEvent::Connected(_) => Ok(None),
+            };
+            match handled {
+                Ok(Some(event)) => return Some(event),
+                Ok(None) => {}
+                Err(msg) => {
+                    self.remote = None;
+                    return Some(GameEvent::Worker(WorkerEvent::Error(msg)));
+                }
+            }
+        }
+    }
+
+    fn remote_message(&mut self, message: NetMessage) -> Result<Option<GameEvent>, String> {
+        let Some(remote) = self.remote.as_ref() else {
+            return Ok(None);
+        };
+        let (hosting, local_name) = (remote.hosting, remote.local_name.clone());
+        match message {
+            NetMessage::Hello { version, name } if hosting => {
+                if version != PROTOCOL_VERSION {
+                    let reason = format!("the host uses protocol version {}", PROTOCOL_VERSION);
+                    let _ = self.send_remote(&NetMessage::Reject { reason });
+                    return Err(format!("the remote player uses protocol version {} instead of {}", version, PROTOCOL_VERSION));
+                }
+                let remote_is_black = self.is_remote(true);
+                let setup = NetMessage::Setup {
+                    version: PROTOCOL_VERSION,
+                    name: local_name.clone(),
+                    host_is_black: !remote_is_black,
+                    position: format_position(self.history.start_state, self.history.black_starts),
+                    moves: self.history.moves.iter().map(|(state, _)| board_to_string(*state)).collect(),
+                    time_control: self.clock.time_control.clone(),
+                };
+                self.send_remote(&setup)?;
+                Ok(Some(GameEvent::RemoteConnected { remote_is_black, remote_name: name, local_name }))
+            }
+            NetMessage::Setup { version, name, host_is_black, position, moves, time_control } if !hosting => {
+                if version != PROTOCOL_VERSION {
+                    return Err(format!("the host uses protocol version {} instead of {}", version, PROTOCOL_VERSION));
+                }
+                self.join_game(host_is_black, &position, &moves, time_control)?;
+                Ok(Some(GameEvent::RemoteConnected { remote_is_black: host_is_black, remote_name: name, local_name }))
+            }
+            NetMessage::Reject { reason } => Err(format!("the host rejected the game: {}", reason)),
+            NetMessage::Move { ply, state } => {
+                let state = board_from_string(&state).map_err(|err| err.to_string())?;
+                if ply != self.history.moves.len() || !self.is_remote(self.game.get_black_tomove()) || self.game_ended() {
+                    return Err(format!("the remote player sent move {} out of turn", ply + 1));
+                }
+                if find_move(&self.game, state).is_none() {
+                    return Err(format!(
+                        "move {} of the remote player is not possible in {}",
+                        ply + 1, format_position(self.game.get_state(), self.game.get_black_tomove())
+                    ));
+                }
+                Ok(Some(GameEvent::Worker(WorkerEvent::MovePlayed { game_id: self.game_id, state })))
+            }
+            _ => Err("unexpected message of the remote player".to_string()),
+        }
+    }
+
+    fn send_remote(&mut self, message: &NetMessage) -> Result<(), String> {
+        match self.remote.as_mut() {
+            Some(remote) => remote.send(message),
+            None => Ok(()),
+        }
+    }
+
+    /// continues the game of the host, every move is checked to be possible
+    fn join_game(&mut self, host_is_black: bool, position: &str, moves: &[String], time_control: TimeControl) -> Result<(), String> {
+        let (history, game) = network::rebuild_game(position, moves).map_err(|msg| format!("invalid game of the host: {}", msg))?;
+        // the local player takes the color the host left
+        if self.is_remote(true) != host_is_black {
+            std::mem::swap(&mut self.black_ai, &mut self.white_ai);
+        }
+        self.time_control = time_control;
+        self.game = game;
+        self.history = history;
+        self.paused = false;
+        self.reset_clock();
+        self.resume_clock();
+        self.worker_thread();
+        if let Some(remote) = self.remote.as_mut() {
+            remote.ready = true;
+        }
+        Ok(())
+    }
+
+    /// hosts or joins the game of a remote player of the current settings, the link of an older game is closed
+    pub fn start_remote(&mut self, local_name: &str) -> Result<(), String> {
+        self.remote = None;
+        let (address, host) = match (&self.black_ai, &self.white_ai) {
+            (PlayerSetting::Remote { .. }, PlayerSetting::Remote { .. }) => return Err("only one player can be a remote player".to_string()),
+            (PlayerSetting::Remote { address, host }, _) | (_, PlayerSetting::Remote { address, host }) => (address.clone(), *host),
+            _ => return Ok(()),
+        };
+        if host {
+            self.remote = Some(RemoteLink::host(&address, local_name, self.repaint_ctx.clone())?);
+        } else {
+            // nobody moves until the game of the host arrives
+            self.stop_worker();
+            self.clock.stop();
+            self.remote = Some(RemoteLink::join(&address, local_name, self.repaint_ctx.clone()));
+        }
+        Ok(())
+    }
+
+    /// lets spectators connect to the game
+    pub fn share_game(&mut self) -> Result<(), String> {
+        if self.spectators.is_none() {
+            self.spectators = Some(SpectatorServer::start(SPECTATOR_PORT)?);
+        }
+        Ok(())
+    }
+
+    pub fn stop_sharing(&mut self) {
+        self.spectators = None;
+    }
+
+    /// number of spectators if the game is shared
+    pub fn spectators(&self) -> Option<usize> {
+        self.spectators.as_ref().map(SpectatorServer::num_spectators)
+    }
+
+    /// sends the whole game to the spectators, needed whenever the game was started or rewound
+    pub fn update_spectators(&self, black_name: &str, white_name: &str) {
+        if let Some(spectators) = self.spectators.as_ref() {
+            spectators.set_game(NetMessage::Game {
+                version: PROTOCOL_VERSION,
+                black_name: black_name.to_string(),
+                white_name: white_name.to_string(),
+                position: format_position(self.history.start_state, self.history.black_starts),
+                moves: self.history.moves.iter().map(|(state, _)| board_to_string(*state)).collect(),
+            });
+        }
+    }
+
+    /// the game is played with a remote player, moves cannot be taken back
+    pub fn has_remote(&self) -> bool {
+        self.remote.is_some()
+    }
+
+    /// the joining instance waits for the game of the host
+    pub fn remote_waiting(&self) -> bool {
+        self.remote.as_ref().is_some_and(|remote| !remote.ready)
+    }
+
+    pub fn undo_move(&mut self) {
+        // undo until a human player has to move, unless only AIs are playing
+        let both_ai = !self.is_human(true) && !self.is_human(false);
+        while let Some((_, black_moved)) = self.history.undo() {
+            if both_ai || self.is_human(black_moved) {
+                break;
+            }
+        }
+    }
+
+    pub fn redo_move(&mut self) {
+        let both_ai = !self.is_human(true) && !self.is_human(false);
+        while let Some((_, black_moved)) = self.history.redo() {
+            // after the redone move the other player has to move
+            if both_ai || self.is_human(!black_moved) {
+                break;
+            }
+        }
+    }
+
+    /// freezes the game, the AI to move stops thinking until the game is resumed
+    pub fn pause(&mut self) {
+        self.paused = true;
+        self.clock.stop();
+        let _ = self.command_sender.send(WorkerCommand::Pause);
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.resume_clock();
+        let _ = self.command_sender.send(WorkerCommand::Resume);
+    }
+
+    /// an AI is searching its move
+    pub fn ai_thinking(&self) -> bool {
+        let is_blacksmove = self.game.get_black_tomove();
+        !self.paused && !self.game_ended() && !self.is_human(is_blacksmove) && !self.is_remote(is_blacksmove)
+    }
+
+    /// the AI to move is an engine that can be told to move at once, a Magister Ludi search cannot be interrupted
+    pub fn can_move_now(&self) -> bool {
+        let setting = if self.game.get_black_tomove() {&self.black_ai} else {&self.white_ai};
+        self.ai_thinking() && matches!(setting, PlayerSetting::ExternalEngine { .. })
+    }
+
+    /// makes the engine to move send its best move at once
+    pub fn move_now(&self) {
+        let _ = self.command_sender.send(WorkerCommand::MoveNow);
+    }
+
+    /// lets a Magister Ludi AI search the best move for the current state in the background
+    pub fn request_hint(&mut self) {
+        let state = self.game.get_state();
+        if self.worker.is_none() {
+            self.spawn_worker();
+        }
+        if self.command_sender.send(WorkerCommand::Hint { state }).is_ok() {
+            self.hint_state = Some(state);
+        }
+    }
+
+    pub fn hint_pending(&self) -> bool {
+        self.hint_state.is_some()
+    }
+
+    pub fn is_human(&self, for_black: bool) -> bool {
+        if for_black {self.black_ai == PlayerSetting::Human} else {self.white_ai == PlayerSetting::Human}
+    }
+
+    pub fn is_remote(&self, for_black: bool) -> bool {
+        matches!(if for_black {&self.black_ai} else {&self.white_ai}, PlayerSetting::Remote { .. })
+    }
+
+    /// starts the AIs for the current state of the game with the current player settings
+    pub fn worker_thread(&mut self) {
+        // nobody has to move in an ended game
+        if self.game_ended() {
+            self.stop_worker();
+            return;
+        }
+        if self.worker.is_none() {
+            self.spawn_worker();
+        }
+        self.game_id += 1;
+        let _ = self.command_sender.send(WorkerCommand::NewGame {
+            game_id: self.game_id,
+            state: self.game.get_state(),
+            black_tomove: self.game.get_black_tomove(),
+            black_ai: self.black_ai.clone(),
+            white_ai: self.white_ai.clone(),
+            clock: self.clock.clone(),
+        });
+    }
+
+    /// lets the AIs continue from the current state of the game after it was rewound
+    pub fn rewind(&mut self) {
+        self.paused = false;
+        self.resume_clock();
+        if self.worker.is_none() || self.game_ended() {
+            self.worker_thread();
+            return;
+        }
+        self.game_id += 1;
+        let _ = self.command_sender.send(WorkerCommand::Undo {
+            game_id: self.game_id,
+            state: self.game.get_state(),
+            black_tomove: self.game.get_black_tomove(),
+            clock: self.clock.clone(),
+        });
+    }
+
+    fn spawn_worker(&mut self) {
+        // every worker gets its own channels, so an old worker cannot take over any messages
+        let (command_sender, command_receiver) = unbounded();
+        let (event_sender, event_receiver) = unbounded();
+        self.worker = Some(Worker::spawn(command_receiver, event_sender, self.repaint_ctx.clone()));
+        self.command_sender = command_sender;
+        self.event_receiver = event_receiver;
+    }
+
+    /// stops the AIs and waits for the worker, searches that are still running are left to end on their own
+    pub fn stop_worker(&mut self) {
+        if let Some(worker) = self.worker.take() {
+            let _ = self.command_sender.send(WorkerCommand::Stop);
+            let _ = worker.join();
+        }
+        self.hint_state = None;
+        // drop events the GUI did not receive
+        while self.event_receiver.try_recv().is_ok() {}
+    }
+}
+
+impl Drop for GameController {
+    fn drop(&mut self) {
+        self.stop_worker();
+    }
+}
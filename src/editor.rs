@@ -0,0 +1,87 @@
+use rustai_abalone::game::{AbaloneGame, Board, Coord, EMPTY_BOARD};
+
+use crate::position::{StartingPosition, START_MARBLES};
+use crate::record::{BLACK_VALUE, WHITE_VALUE};
+
+/// the sixth lost marble ends the game
+const MAX_LOSS: u8 = 5;
+
+/// what a click on a field of the editor board places
+#[derive(PartialEq, Clone, Copy)]
+pub enum EditorTool {
+    BlackMarble,
+    WhiteMarble,
+    Remove,
+}
+
+/// state of the board editor
+pub struct BoardEditor {
+    /// name of the position in the start window
+    pub name: String,
+    pub board: Board,
+    pub black_starts: bool,
+    /// marbles the players have already lost in the edited position
+    pub black_loss: u8,
+    pub white_loss: u8,
+    pub tool: EditorTool,
+    /// result of the last validation
+    pub message: String,
+}
+
+impl BoardEditor {
+    /// starts editing from the given position, the losses are taken from the marbles on the board
+    pub fn new(position: &StartingPosition) -> Self {
+        let (blacks, whites, _) = AbaloneGame::coords_by_type(position.board);
+        Self {
+            name: format!("{} (edited)", position.name),
+            board: position.board,
+            black_starts: position.black_starts,
+            black_loss: START_MARBLES.saturating_sub(blacks.len()) as u8,
+            white_loss: START_MARBLES.saturating_sub(whites.len()) as u8,
+            tool: EditorTool::BlackMarble,
+            message: "".to_string(),
+        }
+    }
+
+    /// applies the current tool to the field, clicking a marble of the chosen color removes it
+    pub fn click_field(&mut self, coord: Coord) {
+        let value = match self.tool {
+            EditorTool::BlackMarble => BLACK_VALUE,
+            EditorTool::WhiteMarble => WHITE_VALUE,
+            EditorTool::Remove => EMPTY_BOARD[coord.x][coord.y],
+        };
+        self.board[coord.x][coord.y] = if self.board[coord.x][coord.y] == value {
+            EMPTY_BOARD[coord.x][coord.y]
+        } else {
+            value
+        };
+        self.message.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.board = EMPTY_BOARD;
+        self.message.clear();
+    }
+
+    /// the position if the marbles on the board fit to the lost marbles and the game has not ended yet
+    pub fn validate(&self) -> Result<StartingPosition, String> {
+        if self.name.trim().is_empty() {
+            return Err("the position needs a name".to_string());
+        }
+        let (blacks, whites, _) = AbaloneGame::coords_by_type(self.board);
+        for (name, marbles, loss) in [("blue", blacks.len(), self.black_loss), ("yellow", whites.len(), self.white_loss)] {
+            if loss > MAX_LOSS {
+                return Err(format!("the {} player may lose at most {} marbles", name, MAX_LOSS));
+            }
+            let expected = START_MARBLES - usize::from(loss);
+            if marbles != expected {
+                return Err(format!("the {} player has {} marbles but should have {}", name, marbles, expected));
+            }
+        }
+        let position = StartingPosition { name: self.name.trim().to_string(), board: self.board, black_starts: self.black_starts };
+        if AbaloneGame::new(position.board).get_game_ended() {
+            return Err("the game has already ended in this position".to_string());
+        }
+        Ok(position)
+    }
+}
@@ -0,0 +1,319 @@
+//! text protocol between the GUI and engine processes, one command per line on stdin and stdout
+//!
+//! GUI to engine:
+//! - `aei`: the engine introduces itself with `id name NAME` and `id author AUTHOR`, lists its options
+//!   with `option name NAME default VALUE` and finishes with `aeiok`
+//! - `setoption name NAME value VALUE`
+//! - `isready`: answered with `readyok` as soon as the engine can take commands
+//! - `newgame`
+//! - `position POSITION`: the position string of `format_position`, e.g. `bb1yy/bbbyyy/1bb1yy1/8/9/8/1yy1bb1/yyybbb/yy1bb b 0 0`
+//! - `go [movetime MS] [nodes N]`: searches a move for the position, without limits the engine chooses its time
+//! - `stop`: the engine sends its best move as soon as possible
+//! - `quit`
+//!
+//! engine to GUI:
+//! - `info [nodes N] [time MS] [winrate RATE] [pv MOVE ...]`: statistics of the running search,
+//!   the win rate is the chance of the player to move between 0 and 1, `pv` has to be last
+//! - `bestmove MOVE`: the chosen move in standard notation, e.g. `A1B2`
+
+use crossbeam_channel::{unbounded, Receiver, RecvTimeoutError};
+use rustai_abalone::game::Board;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::{thread, time};
+
+use crate::analysis::SearchInfo;
+use crate::history::MoveHistory;
+use crate::notation::parse_move;
+use crate::position::format_position;
+
+/// time an engine may take to answer `aei` and `isready`
+const STARTUP_TIMEOUT: time::Duration = time::Duration::from_secs(10);
+/// time an engine may exceed its move time before it is stopped
+const STOP_GRACE: time::Duration = time::Duration::from_secs(2);
+
+/// commands of the GUI, as read by an engine
+#[derive(Debug, PartialEq)]
+pub enum EngineCommand {
+    Aei,
+    SetOption { name: String, value: String },
+    IsReady,
+    NewGame,
+    /// the position string
+    Position(String),
+    Go { movetime: Option<time::Duration>, nodes: Option<usize> },
+    Stop,
+    Quit,
+}
+
+impl EngineCommand {
+    /// reads a command line, unknown commands give `None` and are ignored by engines
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+        match command {
+            "aei" => Some(EngineCommand::Aei),
+            "setoption" => {
+                let option = rest.trim().strip_prefix("name ")?;
+                let (name, value) = option.split_once(" value ").unwrap_or((option, ""));
+                Some(EngineCommand::SetOption { name: name.trim().to_string(), value: value.trim().to_string() })
+            }
+            "isready" => Some(EngineCommand::IsReady),
+            "newgame" => Some(EngineCommand::NewGame),
+            "position" => Some(EngineCommand::Position(rest.trim().to_string())),
+            "go" => {
+                let (mut movetime, mut nodes) = (None, None);
+                let mut tokens = rest.split_whitespace();
+                while let Some(token) = tokens.next() {
+                    match token {
+                        "movetime" => movetime = tokens.next().and_then(|value| value.parse().ok()).map(time::Duration::from_millis),
+                        "nodes" => nodes = tokens.next().and_then(|value| value.parse().ok()),
+                        _ => {}
+                    }
+                }
+                Some(EngineCommand::Go { movetime, nodes })
+            }
+            "stop" => Some(EngineCommand::Stop),
+            "quit" => Some(EngineCommand::Quit),
+            _ => None,
+        }
+    }
+}
+
+/// search statistics of an `info` line
+pub fn parse_info(line: &str, for_black: bool) -> SearchInfo {
+    let mut info = SearchInfo { for_black, ..SearchInfo::default() };
+    let mut tokens = line.split_whitespace().skip(1);
+    while let Some(token) = tokens.next() {
+        match token {
+            "nodes" => info.simulations = tokens.next().and_then(|value| value.parse().ok()).unwrap_or_default(),
+            "time" => {
+                let millis = tokens.next().and_then(|value| value.parse().ok()).unwrap_or_default();
+                info.elapsed = time::Duration::from_millis(millis);
+            }
+            "winrate" => info.win_rate = tokens.next().and_then(|value| value.parse().ok()),
+            "pv" => {
+                info.principal_variation = tokens.by_ref().map(str::to_string).collect();
+            }
+            _ => {}
+        }
+    }
+    info
+}
+
+/// writes the `go` command for the time the engine may think
+pub fn go_command(budget: Option<time::Duration>) -> String {
+    match budget {
+        Some(budget) => format!("go movetime {}", budget.as_millis()),
+        None => "go".to_string(),
+    }
+}
+
+/// a running engine process, it is ended when the player is dropped
+pub struct EngineProcess {
+    /// name the engine gave in its `id` line
+    pub name: String,
+    child: Child,
+    stdin: Arc<Mutex<ChildStdin>>,
+    /// lines of the engine output, read in their own thread
+    lines: Receiver<String>,
+}
+
+impl EngineProcess {
+    /// starts the engine and waits until it is ready
+    pub fn start(command: &str, args: &[String]) -> Result<Self, String> {
+        let mut child = Command::new(command)
+            .args(args.iter().filter(|arg| !arg.is_empty()))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|err| format!("cannot start engine '{}': {}", command, err))?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(format!("cannot talk to engine '{}'", command));
+        };
+        let (line_sender, lines) = unbounded();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    return;
+                };
+                if line_sender.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+        let mut engine = Self { name: command.to_string(), child, stdin: Arc::new(Mutex::new(stdin)), lines };
+        engine.send("aei")?;
+        engine.wait_for("aei", "aeiok")?;
+        engine.send("isready")?;
+        engine.wait_for("isready", "readyok")?;
+        engine.send("newgame")?;
+        Ok(engine)
+    }
+
+    /// reads the lines of the engine until the reply, an engine that does not answer in time is not used
+    fn wait_for(&mut self, command: &str, reply: &str) -> Result<(), String> {
+        let deadline = time::Instant::now() + STARTUP_TIMEOUT;
+        loop {
+            let Some(line) = self.read_line(deadline.saturating_duration_since(time::Instant::now()))? else {
+                return Err(format!("engine {} did not answer {} in time", self.name, command));
+            };
+            if let Some(name) = line.strip_prefix("id name ") {
+                self.name = name.trim().to_string();
+            } else if line.trim() == reply {
+                return Ok(());
+            }
+        }
+    }
+
+    pub fn send(&self, command: &str) -> Result<(), String> {
+        Self::write_command(&self.stdin, command).map_err(|err| format!("engine {} does not take commands: {}", self.name, err))
+    }
+
+    fn write_command(stdin: &Mutex<ChildStdin>, command: &str) -> std::io::Result<()> {
+        let mut stdin = stdin.lock().map_err(|_| std::io::Error::other("engine input is poisoned"))?;
+        writeln!(stdin, "{}", command)?;
+        stdin.flush()
+    }
+
+    /// the next line of the engine, `None` if it did not answer in time
+    fn read_line(&self, timeout: time::Duration) -> Result<Option<String>, String> {
+        match self.lines.recv_timeout(timeout) {
+            Ok(line) => Ok(Some(line)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(format!("engine {} has quit", self.name)),
+        }
+    }
+
+    /// lets the engine stop its search from another thread
+    pub fn stop_handle(&self) -> EngineStop {
+        EngineStop { stdin: self.stdin.clone() }
+    }
+
+    /// lets the engine search the position and returns the state chosen by the engine
+    /// the statistics of its `info` lines are given to `report`
+    pub fn own_move(
+        &mut self, state: Board, black_tomove: bool, budget: Option<time::Duration>, report: &dyn Fn(SearchInfo)
+    ) -> Result<Board, String> {
+        self.send(&format!("position {}", format_position(state, black_tomove)))?;
+        self.send(&go_command(budget))?;
+        let started = time::Instant::now();
+        let mut stop_sent = false;
+        loop {
+            // an engine exceeding its time is stopped, without limits it may think as long as it wants
+            let timeout = match budget {
+                Some(budget) if !stop_sent => (budget + STOP_GRACE).saturating_sub(started.elapsed()),
+                Some(_) => STOP_GRACE,
+                None => time::Duration::from_secs(3600),
+            };
+            let Some(line) = self.read_line(timeout)? else {
+                if stop_sent {
+                    return Err(format!("engine {} did not send a move", self.name));
+                }
+                self.send("stop")?;
+                stop_sent = true;
+                continue;
+            };
+            if line.starts_with("info") {
+                report(parse_info(&line, black_tomove));
+            } else if let Some(notation) = line.strip_prefix("bestmove") {
+                let game = MoveHistory::new_game(state, black_tomove);
+                return match parse_move(&game, notation.trim()) {
+                    Ok((_, _, next_state)) => Ok(next_state),
+                    Err(err) => Err(format!("engine {} sent an invalid move: {}", self.name, err)),
+                };
+            }
+        }
+    }
+}
+
+impl Drop for EngineProcess {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        // engines that ignore `quit` are ended anyway
+        thread::sleep(time::Duration::from_millis(50));
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// sends `stop` to an engine that is searching in another thread
+#[derive(Clone)]
+pub struct EngineStop {
+    stdin: Arc<Mutex<ChildStdin>>,
+}
+
+impl EngineStop {
+    pub fn stop(&self) {
+        let _ = EngineProcess::write_command(&self.stdin, "stop");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(EngineCommand::parse("aei"), Some(EngineCommand::Aei));
+        assert_eq!(EngineCommand::parse("  isready \n"), Some(EngineCommand::IsReady));
+        assert_eq!(EngineCommand::parse("newgame"), Some(EngineCommand::NewGame));
+        assert_eq!(EngineCommand::parse("stop"), Some(EngineCommand::Stop));
+        assert_eq!(EngineCommand::parse("quit"), Some(EngineCommand::Quit));
+        assert_eq!(
+            EngineCommand::parse("setoption name simulations value 400"),
+            Some(EngineCommand::SetOption { name: "simulations".to_string(), value: "400".to_string() })
+        );
+        assert_eq!(
+            EngineCommand::parse("setoption name log file"),
+            Some(EngineCommand::SetOption { name: "log file".to_string(), value: "".to_string() })
+        );
+        assert_eq!(
+            EngineCommand::parse("position bb1yy/bbbyyy/1bb1yy1/8/9/8/1yy1bb1/yyybbb/yy1bb b 0 0"),
+            Some(EngineCommand::Position("bb1yy/bbbyyy/1bb1yy1/8/9/8/1yy1bb1/yyybbb/yy1bb b 0 0".to_string()))
+        );
+        assert_eq!(
+            EngineCommand::parse("go movetime 1500 nodes 300"),
+            Some(EngineCommand::Go { movetime: Some(time::Duration::from_millis(1500)), nodes: Some(300) })
+        );
+        assert_eq!(EngineCommand::parse("go"), Some(EngineCommand::Go { movetime: None, nodes: None }));
+    }
+
+    #[test]
+    fn ignores_unknown_and_malformed_commands() {
+        for line in ["", "   ", "hello", "AEI", "bestmove A1B2", "setoption simulations 400"] {
+            assert_eq!(EngineCommand::parse(line), None, "{}", line);
+        }
+        // words after a command without arguments do not matter
+        assert_eq!(EngineCommand::parse("isready now"), Some(EngineCommand::IsReady));
+        // unknown limits and values that are no numbers are left out
+        assert_eq!(
+            EngineCommand::parse("go movetime soon depth 3 nodes"),
+            Some(EngineCommand::Go { movetime: None, nodes: None })
+        );
+    }
+
+    #[test]
+    fn parses_info_lines() {
+        let info = parse_info("info nodes 2000 time 1250 winrate 0.625 pv A1B2 I9H8 C3C4D3", false);
+        assert!(!info.for_black);
+        assert_eq!((info.simulations, info.elapsed), (2000, time::Duration::from_millis(1250)));
+        assert_eq!(info.win_rate, Some(0.625));
+        assert_eq!(info.principal_variation, ["A1B2", "I9H8", "C3C4D3"]);
+
+        // values that cannot be read are left at their defaults
+        let info = parse_info("info nodes many time winrate high string thinking", true);
+        assert!(info.for_black);
+        assert_eq!((info.simulations, info.elapsed, info.win_rate), (0, time::Duration::ZERO, None));
+        assert!(info.principal_variation.is_empty());
+    }
+
+    #[test]
+    fn writes_go_commands() {
+        assert_eq!(go_command(None), "go");
+        assert_eq!(go_command(Some(time::Duration::from_millis(2500))), "go movetime 2500");
+        assert_eq!(EngineCommand::parse(&go_command(Some(time::Duration::from_secs(3)))),
+            Some(EngineCommand::Go { movetime: Some(time::Duration::from_secs(3)), nodes: None }));
+    }
+}
@@ -0,0 +1,1044 @@
+use rustai_abalone::game::{Board, BELGIAN_DAISY};
+use eframe::egui;
+use egui::{Vec2, Align2};
+use epaint::{pos2, vec2, Color32, Stroke};
+use std::time;
+
+use crate::analysis::{self, SearchInfo};
+use crate::board::AbaloneBoard;
+use crate::clock::TimeControl;
+use crate::constants::{BASE_WIDTH, BASE_HEIGHT, MARBLE_SIZE, DIST_SIZE, VEC_LEN};
+use crate::controller::{GameController, GameEvent};
+use crate::editor::{BoardEditor, EditorTool};
+use crate::history::MoveHistory;
+use crate::labels::AbaloneLabels;
+use crate::network::{self, NetMessage, RemoteEvent, RemoteLink};
+use crate::notation;
+use crate::player_setting::PlayerSetting;
+use crate::position::{self, StartingPosition};
+use crate::settings::{UserSettings, STORAGE_KEY};
+use crate::record::{board_coords, board_from_string, GameRecord};
+use crate::replay::ReplaySettings;
+use crate::worker::WorkerEvent;
+
+#[derive(PartialEq)]
+enum GUIWindow {
+    Start,
+    Game,
+    Replay,
+    Editor,
+    Spectate,
+}
+
+/// the complete application: start window, game window and replay viewer
+pub struct AbaloneGUI {
+    /// game and AI players
+    control: GameController,
+    /// painting and human input
+    board: AbaloneBoard,
+    /// state of the replay viewer
+    replay: ReplaySettings,
+    /// side panel with the search statistics of the AIs
+    show_analysis: bool,
+    search_info: Option<SearchInfo>,
+    /// time the latest search statistics arrived
+    search_info_time: f64,
+    current_window: GUIWindow,
+    starting_positions: Vec<StartingPosition>,
+    selected_index: usize,
+    /// position edited in the editor window
+    editor: BoardEditor,
+    /// dark or light widgets, the board keeps its colors
+    dark_theme: bool,
+    /// connection to the shared game in the spectator window
+    watching: Option<RemoteLink>,
+    glabels: AbaloneLabels,
+}
+
+impl AbaloneGUI {
+    /// the settings of the last launch are restored from the eframe storage
+    pub fn new(cc: &eframe::CreationContext<'_>, board: Board, mut starting_positions: Vec<StartingPosition>) -> Self {
+        let settings: UserSettings = cc.storage
+            .and_then(|storage| eframe::get_value(storage, STORAGE_KEY))
+            .unwrap_or_default();
+        let style = egui::Style {
+            visuals: if settings.dark_theme {egui::Visuals::dark()} else {egui::Visuals::light()},
+            ..egui::Style::default()
+        };
+        cc.egui_ctx.set_style(style);
+
+        if starting_positions.len() < 1 {
+            starting_positions.push(StartingPosition::new("Belgian Daisy", BELGIAN_DAISY));
+        }
+        let mut control = GameController::new(board);
+        control.set_repaint_context(cc.egui_ctx.clone());
+        let mut gui = Self {
+            control,
+            board: AbaloneBoard::new(&cc.egui_ctx),
+            replay: ReplaySettings::default(),
+            show_analysis: false,
+            search_info: None,
+            search_info_time: 0.0,
+            current_window: GUIWindow::Start,
+            editor: BoardEditor::new(&starting_positions[0]),
+            starting_positions,
+            selected_index: 0,
+            dark_theme: settings.dark_theme,
+            watching: None,
+            glabels: AbaloneLabels::default(),
+        };
+        gui.control.black_ai = settings.black_ai;
+        gui.control.white_ai = settings.white_ai;
+        gui.control.time_control = settings.time_control;
+        gui.glabels.black_name = settings.black_name;
+        gui.glabels.white_name = settings.white_name;
+        if !gui.select_position(&settings.position_name) {
+            gui.start_painter_vectors();
+        }
+        gui
+    }
+
+    /// the choices of the start window to be restored on the next launch
+    pub fn user_settings(&self) -> UserSettings {
+        UserSettings {
+            black_ai: self.control.black_ai.clone(),
+            white_ai: self.control.white_ai.clone(),
+            black_name: self.glabels.black_name.clone(),
+            white_name: self.glabels.white_name.clone(),
+            time_control: self.control.time_control.clone(),
+            position_name: self.starting_positions[self.selected_index].name.clone(),
+            dark_theme: self.dark_theme,
+        }
+    }
+
+    /// adds the positions of a position library to the start window
+    pub fn add_positions(&mut self, positions: Vec<StartingPosition>) {
+        position::merge_positions(&mut self.starting_positions, positions);
+        self.start_painter_vectors();
+    }
+
+    /// sets the type and the name of a player as if they were chosen in the start window, missing values are kept
+    pub fn set_player(&mut self, for_black: bool, setting: Option<PlayerSetting>, name: Option<String>) {
+        let (player_set, player_name) = if for_black {
+            (&mut self.control.black_ai, &mut self.glabels.black_name)
+        } else {
+            (&mut self.control.white_ai, &mut self.glabels.white_name)
+        };
+        if let Some(setting) = setting {
+            *player_set = setting;
+        }
+        if let Some(name) = name {
+            *player_name = name;
+        }
+    }
+
+    /// selects the starting position with the given name, returns false if there is none
+    pub fn select_position(&mut self, name: &str) -> bool {
+        match self.starting_positions.iter().position(|position| position.name == name) {
+            Some(index) => {
+                self.selected_index = index;
+                self.start_painter_vectors();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// starts a game from the selected starting position, the start window is skipped
+    pub fn start_selected_game(&mut self) {
+        let position = &self.starting_positions[self.selected_index];
+        self.start_game(position.board, position.black_starts);
+        self.current_window = GUIWindow::Game;
+    }
+
+    /// continues a saved game, the start window is skipped if it could be loaded
+    pub fn load_saved_game(&mut self, path: &str) -> Result<(), String> {
+        self.glabels.record_path = path.to_string();
+        self.load_game();
+        if self.current_window == GUIWindow::Game {
+            Ok(())
+        } else {
+            Err(self.glabels.file_message.clone())
+        }
+    }
+
+    /// replaces the marble images by the ones found in the skin directory
+    pub fn load_skin(&mut self, ctx: &egui::Context, skin_dir: &std::path::Path) {
+        self.board.load_skin(ctx, Some(skin_dir));
+    }
+
+    fn perform_move(&mut self, next_state: Board) {
+        self.show_move(next_state, true);
+    }
+
+    /// updates the game with the chosen state and highlights the moved marbles
+    /// the move is only recorded and sent to the worker if `record` is set
+    fn show_move(&mut self, next_state: Board, record: bool) {
+        self.glabels.move_message = match notation::state_to_notation(&self.control.game, next_state) {
+            Some(move_text) => format!("Last move: {}", move_text),
+            None => "".to_string(),
+        };
+        let move_circles = self.control.game.differences_to_state(next_state);
+        let was_black_last = self.control.game.get_black_tomove();
+
+        // deselect / decolorize selected marbles after performing a move
+        self.board.clear_selection();
+
+        // first update board -> actually perform move
+        if record {
+            self.control.perform_move(next_state);
+        } else {
+            self.control.apply_move(next_state);
+        }
+
+        // now adjust painter values
+        self.board.game_painter_vectors(&self.control.game, &mut self.glabels);
+        self.board.highlight_move(move_circles, was_black_last);
+    }
+
+    /// highlights the marbles and the move button of the suggested state
+    fn show_hint(&mut self, suggested_state: Board) {
+        if let Some((coords, marble_move)) = notation::find_move(&self.control.game, suggested_state) {
+            if let Ok(move_text) = notation::format_move(&self.control.game, &coords, marble_move) {
+                self.glabels.move_message = format!("Hint: {}", move_text);
+            }
+            self.board.show_hint(&self.control.game, &coords, marble_move);
+        }
+    }
+
+    /// rebuilds the game from the move history and restarts the worker from the rebuilt position
+    fn rewind_game(&mut self) {
+        self.show_ply(self.control.history.moves.len());
+        if let Some(black_lost) = self.control.lost_on_time {
+            self.board.set_time_loss_message(black_lost, &mut self.glabels);
+        }
+        self.control.rewind();
+        self.control.update_spectators(&self.glabels.black_name, &self.glabels.white_name);
+    }
+
+    /// rebuilds the game after the given number of moves of the history
+    fn show_ply(&mut self, ply: usize) {
+        self.board.clear_selection();
+        if ply == 0 {
+            self.control.game = self.control.history.replay_game(0);
+            self.board.game_painter_vectors(&self.control.game, &mut self.glabels);
+            self.glabels.move_message.clear();
+        } else {
+            // replay all but the last move silently, the last one is shown with highlights
+            let (last_state, _) = self.control.history.moves[ply - 1];
+            self.control.game = self.control.history.replay_game(ply - 1);
+            self.board.game_painter_vectors(&self.control.game, &mut self.glabels);
+            self.show_move(last_state, false);
+        }
+    }
+
+    fn save_game(&mut self) {
+        let record = GameRecord::new(
+            &self.control.history, &self.glabels.black_name, &self.glabels.white_name, &self.control.black_ai, &self.control.white_ai,
+            &self.control.clock.time_control, self.control.lost_on_time);
+        self.glabels.file_message = match record.save(std::path::Path::new(&self.glabels.record_path)) {
+            Ok(()) => format!("Saved to '{}'", self.glabels.record_path),
+            Err(err) => err.to_string(),
+        };
+    }
+
+    /// reads the game record at the record path, failures are reported in the file message
+    fn read_record(&mut self) -> Option<(MoveHistory, GameRecord)> {
+        let loaded = GameRecord::load(std::path::Path::new(&self.glabels.record_path))
+            .and_then(|record| Ok((record.to_history()?, record)));
+        match loaded {
+            Ok(history_record) => {
+                self.glabels.file_message = format!("Loaded '{}'", self.glabels.record_path);
+                Some(history_record)
+            }
+            Err(err) => {
+                self.glabels.file_message = err.to_string();
+                None
+            }
+        }
+    }
+
+    fn load_game(&mut self) {
+        if let Some((history, record)) = self.read_record() {
+            self.glabels.black_name = record.black_name;
+            self.glabels.white_name = record.white_name;
+            // a shared file must not start an engine or connect anywhere, the configured players are kept instead
+            if record.black_ai.is_safe_to_load() {
+                self.control.black_ai = record.black_ai;
+            }
+            if record.white_ai.is_safe_to_load() {
+                self.control.white_ai = record.white_ai;
+            }
+            self.control.history = history;
+            // the clocks start again from the time control
+            self.control.time_control = record.time_control;
+            self.control.reset_clock();
+            self.control.lost_on_time = record.lost_on_time;
+            // rebuilds the game and starts a new worker for the loaded players
+            self.control.stop_worker();
+            self.rewind_game();
+            self.start_remote();
+            self.current_window = GUIWindow::Game;
+        }
+    }
+
+    fn load_replay(&mut self) {
+        if let Some((history, record)) = self.read_record() {
+            self.control.stop_worker();
+            self.glabels.black_name = record.black_name;
+            self.glabels.white_name = record.white_name;
+            self.control.history = history;
+            self.glabels.black_clock.clear();
+            self.glabels.white_clock.clear();
+            self.replay = ReplaySettings::default();
+            self.show_ply(0);
+            self.current_window = GUIWindow::Replay;
+        }
+    }
+
+    /// starts a new game with the current player settings
+    fn start_game(&mut self, start_state: Board, black_starts: bool) {
+        self.control.start_game(start_state, black_starts);
+        self.search_info = None;
+        self.board.clear_selection();
+        self.board.game_painter_vectors(&self.control.game, &mut self.glabels);
+        self.glabels.move_message.clear();
+        self.start_remote();
+        self.control.update_spectators(&self.glabels.black_name, &self.glabels.white_name);
+    }
+
+    /// hosts or joins the game of a remote player, if there is one
+    fn start_remote(&mut self) {
+        let local_name = if self.control.is_remote(true) {&self.glabels.white_name} else {&self.glabels.black_name};
+        match self.control.start_remote(&local_name.clone()) {
+            Ok(()) if self.control.has_remote() => self.glabels.move_message = "Waiting for the remote player".to_string(),
+            Ok(()) => {}
+            Err(msg) => self.glabels.move_message = msg,
+        }
+    }
+
+    fn start_painter_vectors(&mut self) {
+        let position = &self.starting_positions[self.selected_index];
+        self.board.board_painter_vectors(position.board);
+        self.glabels.game_message = if position.black_starts {
+            position.name.clone()
+        } else {
+            format!("{} (yellow starts)", position.name)
+        };
+    }
+
+    fn start_window(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+
+            let painter = self.board.allocate_board(ui);
+            let geometry = self.board.pos.geometry;
+            let button_size = Vec2::splat(MARBLE_SIZE * geometry.scaling);
+            // print game message
+            painter.text(
+                geometry * pos2(BASE_WIDTH+200.0, BASE_HEIGHT/2.0),
+                Align2::CENTER_CENTER,
+                self.glabels.game_message.clone(),
+                self.board.scaled_font(&self.glabels),
+                Color32::WHITE
+            );
+            painter.extend(self.board.pos.circle_move_empty.clone());
+            self.board.paint_marbles(&painter);
+
+            // left and right switch button for position
+            let left_pos = geometry * pos2(BASE_WIDTH-2.0*DIST_SIZE, BASE_HEIGHT+5.0*MARBLE_SIZE);
+            if self.board.nomove_marble.button(ui, egui::Rect::from_center_size(left_pos, button_size)).clicked() {
+                if self.selected_index == 0 {
+                    self.selected_index = self.starting_positions.len() - 1;
+                } else {
+                    self.selected_index -= 1;
+                }
+                self.start_painter_vectors();
+            };
+            painter.arrow(left_pos, vec2(-VEC_LEN, 0.0) * geometry.scaling, Stroke{width: 3.0 * geometry.scaling, color: Color32::BLACK});
+            let right_pos = geometry * pos2(BASE_WIDTH+8.0*DIST_SIZE, BASE_HEIGHT+5.0*MARBLE_SIZE);
+            if self.board.nomove_marble.button(ui, egui::Rect::from_center_size(right_pos, button_size)).clicked() {
+                self.selected_index += 1;
+                if self.selected_index == self.starting_positions.len() {
+                    self.selected_index = 0;
+                }
+                self.start_painter_vectors();
+            };
+            painter.arrow(right_pos, vec2(VEC_LEN, 0.0) * geometry.scaling, Stroke{width: 3.0 * geometry.scaling, color: Color32::BLACK});
+
+            ui.end_row();
+            // standard stuff up here
+            let mut child_ui = ui.new_child(egui::UiBuilder::new().max_rect(self.board.controls_rect(ui)));
+            egui::Grid::new("game_settings")
+                .num_columns(10)
+                .min_col_width(100.0)
+                .show(&mut child_ui, |cui| {
+                    // first row, two buttons, start & end game
+                    cui.label("");
+                    let edit = self.add_another_button(cui, "Editor".to_string());
+                    if edit.clicked() {
+                        self.editor = BoardEditor::new(&self.starting_positions[self.selected_index]);
+                        self.current_window = GUIWindow::Editor;
+                    }
+                    let start = self.add_another_button(cui, "Start Game!".to_string());
+                    if start.clicked() {
+                        self.start_selected_game();
+                    }
+                    self.add_exit_button(cui);
+                    cui.end_row();
+
+                    // second row black player settings
+                    cui.label("Blue Player");
+                    cui.add(egui::TextEdit::singleline(&mut self.glabels.black_name).hint_text("player name"));
+                    self.start_player_options(cui, true);
+                    cui.end_row();
+                    cui.label("Yellow Player");
+                    cui.add(egui::TextEdit::singleline(&mut self.glabels.white_name).hint_text("player name"));
+                    self.start_player_options(cui, false);
+                    cui.end_row();
+
+                    cui.label("Time control");
+                    self.start_time_options(cui);
+                    cui.end_row();
+
+                    cui.label("Appearance");
+                    if cui.checkbox(&mut self.dark_theme, "dark theme").changed() {
+                        cui.ctx().set_visuals(if self.dark_theme {egui::Visuals::dark()} else {egui::Visuals::light()});
+                    }
+                    cui.end_row();
+
+                    // last row: continue a saved game
+                    cui.label("Game file");
+                    cui.add(egui::TextEdit::singleline(&mut self.glabels.record_path).hint_text("path to saved game"));
+                    if cui.button("Load").clicked() {
+                        self.load_game();
+                    }
+                    if cui.button("Replay").clicked() {
+                        self.load_replay();
+                    }
+                    cui.label(self.glabels.file_message.clone());
+                    cui.end_row();
+
+                    // read-only view of a game shared by another instance
+                    cui.label("Watch game");
+                    cui.add(egui::TextEdit::singleline(&mut self.glabels.watch_address).hint_text("address of the shared game"));
+                    if cui.button("Watch").clicked() {
+                        let address = self.glabels.watch_address.clone();
+                        self.watch_game(cui.ctx(), &address);
+                    }
+                    cui.end_row();
+            });
+        });
+    }
+
+    fn start_player_options(&mut self, ui: &mut egui::Ui, for_black: bool) {
+        let id_salt = if for_black {"black"} else {"white"};
+        let player_set = if for_black {&mut self.control.black_ai} else {&mut self.control.white_ai};
+        let selec_text = match player_set {
+            PlayerSetting::Human => "Human Player",
+            PlayerSetting::Random => "Random Player",
+            PlayerSetting::Greedy => "Greedy Player",
+            PlayerSetting::MagisterLudiAI { .. } => "Magister Ludi AI",
+            PlayerSetting::Remote { .. } => "Remote Player",
+            PlayerSetting::ExternalEngine { .. } => "External Engine",
+        };
+        egui::ComboBox::new(id_salt, "Player type")
+            .selected_text(selec_text)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(
+                    player_set,
+                    PlayerSetting::Human,
+                    "Human player");
+                ui.selectable_value(
+                    player_set,
+                    PlayerSetting::Random,
+                    "Random player");
+                ui.selectable_value(
+                    player_set,
+                    PlayerSetting::Greedy,
+                    "Greedy player");
+                ui.selectable_value(
+                    player_set,
+                    PlayerSetting::default_ai(),
+                    "Magister Ludi AI");
+                ui.selectable_value(
+                    player_set,
+                    PlayerSetting::default_host(),
+                    "Remote player");
+                ui.selectable_value(
+                    player_set,
+                    PlayerSetting::ExternalEngine { command: "".to_string(), args: Vec::new() },
+                    "External engine");
+            });
+        match player_set {
+            PlayerSetting::Human => {},
+            PlayerSetting::Random => {
+                ui.label("plays random moves").on_hover_text("The same moves are chosen in every game");
+            }
+            PlayerSetting::Greedy => {
+                ui.label("ejects marbles, else goes to the center")
+                    .on_hover_text("Chooses the move that ejects the most marbles, otherwise the one that brings its marbles closest to the center");
+            }
+            PlayerSetting::MagisterLudiAI { mcts_num, mcts_parallel, mcts_minimum, mcts_depth, ponder } => {
+                (
+                    ui.label("simulations:") | ui.add(egui::DragValue::new(mcts_num).speed(10).range(100..=1000))
+                ).on_hover_text("The number of simulation the 'Magister Ludi' AI will perform to determine a move");
+                (
+                    ui.label("threads:") | ui.add(egui::DragValue::new(mcts_parallel).speed(1).range(1..=50))
+                ).on_hover_text("The number of threads created for the 'Magister Ludi' AI.");
+                (
+                    ui.label("minimum:") | ui.add(egui::DragValue::new(mcts_minimum).speed(1).range(1..=20))
+                ).on_hover_text("The minimum number every selected position will be simulated");
+                (
+                    ui.label("depth:") | ui.add(egui::DragValue::new(mcts_depth).speed(1).range(0..=100))
+                ).on_hover_text("The number of moves that will be performed for each simulation. depth = 0 means unlimited depth");
+                ui.checkbox(ponder, "ponder")
+                    .on_hover_text("The AI predicts the move of its opponent and searches its answer while the opponent thinks");
+            }
+            PlayerSetting::Remote { address, host } => {
+                ui.checkbox(host, "host")
+                    .on_hover_text("This game waits for the remote player, otherwise it joins the game hosted on the address");
+                (
+                    ui.label("address:") | ui.add(egui::TextEdit::singleline(address).hint_text("host:port"))
+                ).on_hover_text("Address to host the game on or of the host to join, the port is 7777 if none is given");
+            }
+            PlayerSetting::ExternalEngine { command, args } => {
+                (
+                    ui.label("command:") | ui.add(egui::TextEdit::singleline(command).hint_text("path of the engine"))
+                ).on_hover_text("Program speaking the engine protocol, it is started for every game");
+                // the arguments are edited as one line, empty ones are left out when the engine is started
+                let mut args_line = args.join(" ");
+                let args_edit = ui.label("arguments:") | ui.add(egui::TextEdit::singleline(&mut args_line));
+                if args_edit.changed() {
+                    *args = args_line.split(' ').map(str::to_string).collect();
+                }
+                args_edit.on_hover_text("Arguments given to the engine, separated by spaces");
+            }
+        }
+    }
+
+    fn start_time_options(&mut self, ui: &mut egui::Ui) {
+        let time_control = &mut self.control.time_control;
+        egui::ComboBox::new("time_control", "")
+            .selected_text(time_control.name())
+            .show_ui(ui, |ui| {
+                for option in [
+                    TimeControl::Unlimited,
+                    TimeControl::SuddenDeath { minutes: 10 },
+                    TimeControl::Fischer { minutes: 5, increment: 5 },
+                    TimeControl::ByoYomi { minutes: 5, periods: 3, period: 30 },
+                ] {
+                    let name = option.name();
+                    ui.selectable_value(time_control, option, name);
+                }
+            });
+        match time_control {
+            TimeControl::Unlimited => {},
+            TimeControl::SuddenDeath { minutes } => {
+                ui.label("minutes:");
+                ui.add(egui::DragValue::new(minutes).speed(1).range(1..=180));
+            }
+            TimeControl::Fischer { minutes, increment } => {
+                ui.label("minutes:");
+                ui.add(egui::DragValue::new(minutes).speed(1).range(1..=180));
+                (
+                    ui.label("increment:") | ui.add(egui::DragValue::new(increment).speed(1).range(0..=60))
+                ).on_hover_text("Seconds added to the clock after every move");
+            }
+            TimeControl::ByoYomi { minutes, periods, period } => {
+                ui.label("minutes:");
+                ui.add(egui::DragValue::new(minutes).speed(1).range(0..=180));
+                (
+                    ui.label("periods:") | ui.add(egui::DragValue::new(periods).speed(1).range(1..=10))
+                ).on_hover_text("The number of periods a player may exceed after the main time");
+                (
+                    ui.label("seconds:") | ui.add(egui::DragValue::new(period).speed(1).range(5..=300))
+                ).on_hover_text("The time for every move after the main time");
+            }
+        }
+    }
+
+    fn game_window(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self.control.check_flag() {
+            let black_lost = self.control.game.get_black_tomove();
+            self.board.set_time_loss_message(black_lost, &mut self.glabels);
+        }
+        self.glabels.black_clock = self.control.clock.display(true);
+        self.glabels.white_clock = self.control.clock.display(false);
+        if self.control.clock.is_running() {
+            ctx.request_repaint_after(time::Duration::from_millis(100));
+        }
+        let mut ai_state = None;
+        while let Some(event) = self.control.receive_event() {
+            match event {
+                GameEvent::Worker(WorkerEvent::MovePlayed { game_id: _, state }) => ai_state = Some(state),
+                GameEvent::Worker(WorkerEvent::Hint { state: _, suggested }) => self.show_hint(suggested),
+                GameEvent::Worker(WorkerEvent::SearchInfo { game_id: _, info }) => {
+                    self.search_info = Some(info);
+                    self.search_info_time = ctx.input(|i| i.time);
+                }
+                GameEvent::Worker(WorkerEvent::Error(msg)) => self.glabels.move_message = msg,
+                GameEvent::RemoteConnected { remote_is_black, remote_name, local_name } => {
+                    self.glabels.move_message = format!("{} joined the game", remote_name);
+                    let (remote_label, local_label) = if remote_is_black {
+                        (&mut self.glabels.black_name, &mut self.glabels.white_name)
+                    } else {
+                        (&mut self.glabels.white_name, &mut self.glabels.black_name)
+                    };
+                    *remote_label = remote_name;
+                    *local_label = local_name;
+                    // the joining instance continues the game of the host
+                    self.board.clear_selection();
+                    self.board.game_painter_vectors(&self.control.game, &mut self.glabels);
+                    self.control.update_spectators(&self.glabels.black_name, &self.glabels.white_name);
+                }
+            }
+        }
+        if self.show_analysis {
+            self.analysis_panel(ctx);
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            // check whether the current player is an AI-player or if the game has already ended
+            let is_active = self.control.is_human(self.control.game.get_black_tomove()) && !self.control.game_ended() && !self.control.paused
+                && !self.control.remote_waiting();
+            let mut selected_next_state = self.board.show(ui, &self.control.game, is_active, &self.glabels);
+            if !is_active {
+                // next state from ai
+                if let Some(board_sent) = ai_state {
+                    selected_next_state = Some(board_sent);
+                }
+            }
+
+            ui.end_row();
+            // standard stuff up here
+            let mut child_ui = ui.new_child(egui::UiBuilder::new().max_rect(self.board.controls_rect(ui)));
+            egui::Grid::new("exit_buttons")
+                .num_columns(6)
+                .min_col_width(150.0)
+                .start_row(2)
+                .show(&mut child_ui, |cui| {
+                    let undo = self.add_enabled_button(cui, self.control.history.can_undo() && !self.control.has_remote(), "Undo".to_string());
+                    if undo.clicked() {
+                        self.control.undo_move();
+                        self.rewind_game();
+                        // AI moves of this frame belong to the old position
+                        selected_next_state = None;
+                    }
+                    let redo = self.add_enabled_button(cui, self.control.history.can_redo() && !self.control.has_remote(), "Redo".to_string());
+                    if redo.clicked() {
+                        self.control.redo_move();
+                        self.rewind_game();
+                        selected_next_state = None;
+                    }
+                    let hint_text = if self.control.hint_pending() {"Thinking..."} else {"Hint"};
+                    let hint = self.add_enabled_button(cui, is_active && !self.control.hint_pending(), hint_text.to_string());
+                    if hint.clicked() {
+                        self.control.request_hint();
+                    }
+                    let analysis_text = if self.show_analysis {"Hide analysis"} else {"Analysis"};
+                    if self.add_another_button(cui, analysis_text.to_string()).clicked() {
+                        self.show_analysis = !self.show_analysis;
+                    }
+                    let quit = self.add_another_button(cui, "Quit".to_string());
+                    if quit.clicked() {
+                        self.control.stop_worker();
+                        self.start_painter_vectors();
+                        self.current_window = GUIWindow::Start;
+                    }
+                    self.add_exit_button(cui);
+                    cui.end_row();
+
+                    cui.add(egui::TextEdit::singleline(&mut self.glabels.record_path).hint_text("path to saved game"));
+                    if self.add_another_button(cui, "Save".to_string()).clicked() {
+                        self.save_game();
+                    }
+                    if self.add_another_button(cui, "Load".to_string()).clicked() {
+                        self.load_game();
+                        selected_next_state = None;
+                    }
+                    cui.label(self.glabels.file_message.clone());
+                    cui.end_row();
+
+                    // positions can be shared as text, a pasted position starts a new game
+                    cui.add(egui::TextEdit::singleline(&mut self.glabels.position_text).hint_text("position"));
+                    if self.add_another_button(cui, "Copy position".to_string()).clicked() {
+                        self.glabels.position_text = position::format_position(self.control.game.get_state(), self.control.game.get_black_tomove());
+                        cui.ctx().copy_text(self.glabels.position_text.clone());
+                    }
+                    if self.add_another_button(cui, "Paste position".to_string()).clicked() {
+                        match position::parse_position(&self.glabels.position_text) {
+                            Ok((board, black_tomove)) => {
+                                self.start_game(board, black_tomove);
+                                selected_next_state = None;
+                            }
+                            Err(err) => self.glabels.move_message = err.to_string(),
+                        }
+                    }
+                    let share_text = if self.control.spectators().is_some() {"Stop sharing"} else {"Share"};
+                    if self.add_another_button(cui, share_text.to_string()).clicked() {
+                        self.toggle_sharing();
+                    }
+                    if let Some(num_spectators) = self.control.spectators() {
+                        cui.label(format!("{} watching on port {}", num_spectators, network::SPECTATOR_PORT));
+                    }
+                    cui.end_row();
+
+                    // moves can also be typed in standard notation
+                    cui.add(egui::TextEdit::singleline(&mut self.glabels.move_input).hint_text("move, e.g. A1B2"));
+                    if self.add_enabled_button(cui, is_active, "Play".to_string()).clicked() {
+                        match notation::parse_move(&self.control.game, &self.glabels.move_input) {
+                            Ok((_, _, next_state)) => {
+                                self.glabels.move_input.clear();
+                                selected_next_state = Some(next_state);
+                            }
+                            Err(err) => self.glabels.move_message = err.to_string(),
+                        }
+                    }
+                    let pause_text = if self.control.paused {"Resume"} else {"Pause"};
+                    if self.add_enabled_button(cui, !self.control.game_ended(), pause_text.to_string()).clicked() {
+                        if self.control.paused {
+                            self.control.resume();
+                            self.glabels.move_message.clear();
+                        } else {
+                            self.control.pause();
+                            self.glabels.move_message = "Game paused".to_string();
+                        }
+                    }
+                    let move_now = self.add_enabled_button(cui, self.control.can_move_now(), "Move now".to_string())
+                        .on_disabled_hover_text("Only engines can be told to move at once, Magister Ludi always finishes its search");
+                    if move_now.clicked() {
+                        self.control.move_now();
+                    }
+                    cui.label(self.glabels.move_message.clone());
+                    cui.end_row();
+            });
+
+            match selected_next_state {
+                Some(n) => self.perform_move(n),
+                _ => {}
+            }
+        });
+    }
+
+    fn analysis_panel(&mut self, ctx: &egui::Context) {
+        let (black_loss, white_loss) = self.control.game.get_black_white_loss();
+        let (blue_chance, from_engine) = analysis::blue_evaluation(self.search_info.as_ref(), black_loss, white_loss);
+        egui::SidePanel::right("analysis_panel").min_width(250.0).show(ctx, |ui| {
+            ui.heading("Analysis");
+
+            // evaluation bar, blue from the left and yellow from the right
+            let (bar_rect, _) = ui.allocate_exact_size(vec2(ui.available_width(), 24.0), egui::Sense::hover());
+            let split = bar_rect.left() + blue_chance.clamp(0.0, 1.0) * bar_rect.width();
+            let painter = ui.painter();
+            painter.rect_filled(bar_rect, 4.0, self.board.colors.color_white_stroke);
+            painter.rect_filled(
+                egui::Rect::from_min_max(bar_rect.min, pos2(split, bar_rect.bottom())), 4.0, self.board.colors.color_black_stroke);
+            let source = if from_engine {"engine win rate"} else {"lost marbles"};
+            ui.label(format!("Blue {:.0}% - Yellow {:.0}% ({})", 100.0 * blue_chance, 100.0 * (1.0 - blue_chance), source));
+            ui.small("Magister Ludi reports its simulations and its thinking time when it has moved. \
+                Win rate and principal variation are only shown for engines that send them, \
+                without a win rate the bar compares the lost marbles.");
+            ui.separator();
+
+            let Some(info) = &self.search_info else {
+                ui.label("No AI has searched yet");
+                return;
+            };
+            let player_name = if info.for_black {&self.glabels.black_name} else {&self.glabels.white_name};
+            if info.finished {
+                ui.label(format!("'{}' searched {:.1} s", player_name, info.elapsed.as_secs_f64()));
+            } else {
+                let thinking = ctx.input(|i| i.time) - self.search_info_time;
+                ui.label(format!("'{}' is thinking for {:.0} s", player_name, thinking));
+                ctx.request_repaint_after(time::Duration::from_millis(500));
+            }
+            ui.label(format!("simulations: {}", info.simulations));
+            if let Some(speed) = info.simulations_per_second() {
+                ui.label(format!("simulations per second: {:.0}", speed));
+            }
+            if let Some(win_rate) = info.win_rate {
+                ui.label(format!("win rate: {:.1}%", 100.0 * win_rate));
+            }
+            if !info.principal_variation.is_empty() {
+                ui.separator();
+                ui.label("principal variation:");
+                ui.label(info.principal_variation.join(" "));
+            }
+        });
+    }
+
+    fn replay_window(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // automatic moves
+        if self.replay.playing {
+            let now = ctx.input(|i| i.time);
+            if self.replay.ply >= self.control.history.moves.len() {
+                self.replay.playing = false;
+            } else if now - self.replay.last_step >= self.replay.speed {
+                self.replay.last_step = now;
+                self.replay.ply += 1;
+                self.show_ply(self.replay.ply);
+            }
+            ctx.request_repaint_after(time::Duration::from_millis(50));
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            // nothing can be clicked, so all marbles are images
+            self.board.show_static(ui, &self.glabels);
+            ui.end_row();
+            // standard stuff up here
+            let mut child_ui = ui.new_child(egui::UiBuilder::new().max_rect(self.board.controls_rect(ui)));
+            let num_moves = self.control.history.moves.len();
+            let mut shown_ply = self.replay.ply;
+            egui::Grid::new("replay_buttons")
+                .num_columns(6)
+                .min_col_width(150.0)
+                .show(&mut child_ui, |cui| {
+                    if self.add_enabled_button(cui, shown_ply > 0, "Back".to_string()).clicked() {
+                        shown_ply -= 1;
+                    }
+                    let play_text = if self.replay.playing {"Pause"} else {"Play"};
+                    if self.add_enabled_button(cui, shown_ply < num_moves, play_text.to_string()).clicked() {
+                        self.replay.playing = !self.replay.playing;
+                        self.replay.last_step = ctx.input(|i| i.time);
+                    }
+                    if self.add_enabled_button(cui, shown_ply < num_moves, "Forward".to_string()).clicked() {
+                        shown_ply += 1;
+                    }
+                    let quit = self.add_another_button(cui, "Quit".to_string());
+                    if quit.clicked() {
+                        self.replay.playing = false;
+                        self.start_painter_vectors();
+                        self.current_window = GUIWindow::Start;
+                    }
+                    self.add_exit_button(cui);
+                    cui.end_row();
+
+                    cui.add(egui::Slider::new(&mut shown_ply, 0..=num_moves).text("move"));
+                    (
+                        cui.label("seconds per move:") | cui.add(egui::DragValue::new(&mut self.replay.speed).speed(0.1).range(0.1..=10.0))
+                    ).on_hover_text("The time between two moves when the replay is played automatically");
+                    cui.label(self.glabels.move_message.clone());
+                    cui.end_row();
+            });
+
+            // jump to the chosen move
+            if shown_ply != self.replay.ply && self.current_window == GUIWindow::Replay {
+                self.replay.ply = shown_ply;
+                self.show_ply(shown_ply);
+            }
+        });
+    }
+
+    fn editor_window(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let painter = self.board.allocate_board(ui);
+            let geometry = self.board.pos.geometry;
+            painter.text(
+                geometry * pos2(BASE_WIDTH+200.0, BASE_HEIGHT/2.0),
+                Align2::CENTER_CENTER,
+                "Edit starting position",
+                self.board.scaled_font(&self.glabels),
+                Color32::WHITE
+            );
+
+            // every field of the board can be clicked
+            let field_size = Vec2::splat(MARBLE_SIZE * geometry.scaling);
+            for coord in board_coords() {
+                let rect = egui::Rect::from_center_size(AbaloneBoard::coord_to_center(coord, geometry), field_size);
+                let id = ui.id().with(("editor_field", coord.x, coord.y));
+                if ui.interact(rect, id, egui::Sense::click()).clicked() {
+                    self.editor.click_field(coord);
+                }
+            }
+            self.board.board_painter_vectors(self.editor.board);
+            painter.extend(self.board.pos.circle_move_empty.clone());
+            self.board.paint_marbles(&painter);
+
+            ui.end_row();
+            let mut child_ui = ui.new_child(egui::UiBuilder::new().max_rect(self.board.controls_rect(ui)));
+            egui::Grid::new("editor_settings")
+                .num_columns(6)
+                .min_col_width(100.0)
+                .show(&mut child_ui, |cui| {
+                    let add = self.add_another_button(cui, "Add position".to_string());
+                    if add.clicked() {
+                        match self.editor.validate() {
+                            Ok(new_position) => {
+                                let name = new_position.name.clone();
+                                position::merge_positions(&mut self.starting_positions, vec![new_position]);
+                                self.selected_index = self.starting_positions.iter().position(|p| p.name == name).unwrap_or(0);
+                                self.start_painter_vectors();
+                                self.current_window = GUIWindow::Start;
+                            }
+                            Err(msg) => self.editor.message = msg,
+                        }
+                    }
+                    if self.add_another_button(cui, "Clear".to_string()).clicked() {
+                        self.editor.clear();
+                    }
+                    let quit = self.add_another_button(cui, "Quit".to_string());
+                    if quit.clicked() {
+                        self.start_painter_vectors();
+                        self.current_window = GUIWindow::Start;
+                    }
+                    self.add_exit_button(cui);
+                    cui.label(self.editor.message.clone());
+                    cui.end_row();
+
+                    cui.label("Name");
+                    cui.add(egui::TextEdit::singleline(&mut self.editor.name).hint_text("position name"));
+                    cui.end_row();
+
+                    cui.label("Click places");
+                    cui.radio_value(&mut self.editor.tool, EditorTool::BlackMarble, "blue marble");
+                    cui.radio_value(&mut self.editor.tool, EditorTool::WhiteMarble, "yellow marble");
+                    cui.radio_value(&mut self.editor.tool, EditorTool::Remove, "nothing");
+                    cui.end_row();
+
+                    cui.label("First move");
+                    cui.radio_value(&mut self.editor.black_starts, true, "blue player");
+                    cui.radio_value(&mut self.editor.black_starts, false, "yellow player");
+                    cui.end_row();
+
+                    cui.label("Lost marbles");
+                    (
+                        cui.label("blue:") | cui.add(egui::DragValue::new(&mut self.editor.black_loss).speed(1).range(0..=5))
+                    ).on_hover_text("The marbles the blue player has lost before the game starts");
+                    (
+                        cui.label("yellow:") | cui.add(egui::DragValue::new(&mut self.editor.white_loss).speed(1).range(0..=5))
+                    ).on_hover_text("The marbles the yellow player has lost before the game starts");
+                    cui.end_row();
+            });
+        });
+    }
+
+    /// lets spectators watch the game or stops sharing it
+    fn toggle_sharing(&mut self) {
+        if self.control.spectators().is_some() {
+            self.control.stop_sharing();
+            return;
+        }
+        match self.control.share_game() {
+            Ok(()) => self.control.update_spectators(&self.glabels.black_name, &self.glabels.white_name),
+            Err(msg) => self.glabels.move_message = msg,
+        }
+    }
+
+    /// connects to a shared game and shows it in the spectator window
+    pub fn watch_game(&mut self, ctx: &egui::Context, address: &str) {
+        self.control.stop_worker();
+        self.control.clock.stop();
+        self.watching = Some(RemoteLink::watch(address, Some(ctx.clone())));
+        self.glabels.black_clock.clear();
+        self.glabels.white_clock.clear();
+        self.glabels.move_message = format!("Connecting to {}", address);
+        self.current_window = GUIWindow::Spectate;
+    }
+
+    /// applies the messages of the shared game, returns an error if the connection has to be closed
+    fn spectator_message(&mut self, message: NetMessage) -> Result<(), String> {
+        match message {
+            NetMessage::Game { version, black_name, white_name, position, moves } => {
+                if version != network::PROTOCOL_VERSION {
+                    return Err(format!("the game is shared with protocol version {}", version));
+                }
+                let (history, game) = network::rebuild_game(&position, &moves)?;
+                self.control.history = history;
+                self.control.game = game;
+                self.glabels.black_name = black_name;
+                self.glabels.white_name = white_name;
+                self.show_ply(self.control.history.moves.len());
+            }
+            NetMessage::Move { ply, state } => {
+                let state = board_from_string(&state).map_err(|err| err.to_string())?;
+                if ply != self.control.history.moves.len() || notation::find_move(&self.control.game, state).is_none() {
+                    return Err(format!("move {} of the shared game is not possible", ply + 1));
+                }
+                self.control.history.moves.push((state, self.control.game.get_black_tomove()));
+                self.show_move(state, false);
+            }
+            NetMessage::Reject { reason } => return Err(reason),
+            _ => return Err("unexpected message of the shared game".to_string()),
+        }
+        Ok(())
+    }
+
+    fn spectate_window(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        while let Some(event) = self.watching.as_mut().and_then(RemoteLink::try_recv) {
+            let handled = match event {
+                RemoteEvent::Message(message) => self.spectator_message(message),
+                RemoteEvent::Closed(msg) => Err(msg),
+                RemoteEvent::Connected(_) => Ok(()),
+            };
+            if let Err(msg) = handled {
+                self.glabels.move_message = msg;
+                self.watching = None;
+            }
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            // nothing can be clicked, the moves come from the shared game
+            self.board.show_static(ui, &self.glabels);
+            ui.end_row();
+            let mut child_ui = ui.new_child(egui::UiBuilder::new().max_rect(self.board.controls_rect(ui)));
+            egui::Grid::new("spectator_buttons")
+                .num_columns(3)
+                .min_col_width(150.0)
+                .show(&mut child_ui, |cui| {
+                    let quit = self.add_another_button(cui, "Quit".to_string());
+                    if quit.clicked() {
+                        self.watching = None;
+                        self.start_painter_vectors();
+                        self.current_window = GUIWindow::Start;
+                    }
+                    self.add_exit_button(cui);
+                    cui.label(self.glabels.move_message.clone());
+                    cui.end_row();
+            });
+        });
+    }
+
+    fn add_exit_button(&self, ui: &mut egui::Ui) {
+        if ui.add(
+            egui::Button::new(
+                egui::RichText::new("Exit").size(30.0)
+            )
+        ).clicked() {
+            // game exit here
+        }
+    }
+
+    fn add_another_button(&self, ui: &mut egui::Ui, button_text: String) -> egui::Response {
+        ui.add(egui::Button::new(
+                egui::RichText::new(button_text).size(30.0)
+            )
+        )
+    }
+
+    fn add_enabled_button(&self, ui: &mut egui::Ui, enabled: bool, button_text: String) -> egui::Response {
+        ui.add_enabled(enabled, egui::Button::new(
+                egui::RichText::new(button_text).size(30.0)
+            )
+        )
+    }
+}
+
+impl eframe::App for AbaloneGUI {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        match self.current_window {
+            GUIWindow::Start => self.start_window(ctx, frame),
+            GUIWindow::Game => self.game_window(ctx, frame),
+            GUIWindow::Replay => self.replay_window(ctx, frame),
+            GUIWindow::Editor => self.editor_window(ctx, frame),
+            GUIWindow::Spectate => self.spectate_window(ctx, frame),
+        }
+    }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, STORAGE_KEY, &self.user_settings());
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.control.stop_worker();
+    }
+}
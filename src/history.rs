@@ -0,0 +1,74 @@
+use rustai_abalone::game::{AbaloneGame, Board};
+
+use crate::side::SidedGame;
+
+pub struct MoveHistory {
+    /// board the game was started from
+    pub start_state: Board,
+    /// the blue player has the first move
+    pub black_starts: bool,
+    /// performed moves as (state chosen by the mover, blue player moved)
+    pub moves: Vec<(Board, bool)>,
+    /// undone moves that can be redone, the next one to redo is last
+    pub undone: Vec<(Board, bool)>,
+}
+
+impl MoveHistory {
+    pub fn new(start_state: Board, black_starts: bool) -> Self {
+        Self {
+            start_state,
+            black_starts,
+            moves: Vec::with_capacity(200),
+            undone: Vec::new(),
+        }
+    }
+
+    /// records a new move, any undone moves are discarded
+    pub fn push(&mut self, next_state: Board, black_moved: bool) {
+        self.moves.push((next_state, black_moved));
+        self.undone.clear();
+    }
+
+    pub fn undo(&mut self) -> Option<(Board, bool)> {
+        let last = self.moves.pop()?;
+        self.undone.push(last);
+        Some(last)
+    }
+
+    pub fn redo(&mut self) -> Option<(Board, bool)> {
+        let next = self.undone.pop()?;
+        self.moves.push(next);
+        Some(next)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.moves.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// applies a chosen state to the game the same way the GUI does after a move
+    pub fn apply_state(game: &mut SidedGame, next_state: Board, black_moved: bool) {
+        if black_moved {
+            game.update_state(AbaloneGame::rotate_board(next_state));
+        } else {
+            game.update_state(next_state);
+        }
+    }
+
+    /// creates a game from the starting board with the player to move
+    pub fn new_game(start_state: Board, black_starts: bool) -> SidedGame {
+        SidedGame::new(start_state, black_starts)
+    }
+
+    /// rebuilds the game from the starting position up to (excluding) move number `ply`
+    pub fn replay_game(&self, ply: usize) -> SidedGame {
+        let mut game = Self::new_game(self.start_state, self.black_starts);
+        for (next_state, black_moved) in self.moves.iter().take(ply) {
+            Self::apply_state(&mut game, *next_state, *black_moved);
+        }
+        game
+    }
+}
@@ -4,6 +4,21 @@ pub struct AbaloneLabels {
     pub game_message: String,
     pub white_name: String,
     pub black_name: String,
+    /// move typed in standard notation
+    pub move_input: String,
+    /// last performed move or the reason a typed move was rejected
+    pub move_message: String,
+    /// path for saving and loading games
+    pub record_path: String,
+    /// result of the last save or load
+    pub file_message: String,
+    /// address of a shared game to watch
+    pub watch_address: String,
+    /// compact position string of the game for copying and pasting
+    pub position_text: String,
+    /// remaining time of the players, empty without clocks
+    pub black_clock: String,
+    pub white_clock: String,
     pub font: FontId,
 }
 
@@ -13,6 +28,14 @@ impl Default for AbaloneLabels {
             game_message: "".to_string(),
             black_name: "Blue Player".to_string(),
             white_name: "Yellow Player".to_string(),
+            move_input: "".to_string(),
+            move_message: "".to_string(),
+            record_path: "abalone_game.json".to_string(),
+            file_message: "".to_string(),
+            watch_address: "127.0.0.1".to_string(),
+            position_text: "".to_string(),
+            black_clock: "".to_string(),
+            white_clock: "".to_string(),
             font: FontId::proportional(30.0)
         }
     }
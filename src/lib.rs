@@ -0,0 +1,41 @@
+pub mod analysis;
+pub mod baseline;
+#[cfg(feature = "gui")]
+pub mod board;
+pub mod clock;
+#[cfg(feature = "gui")]
+pub mod coloring;
+pub mod constants;
+#[cfg(feature = "gui")]
+pub mod controller;
+pub mod engine;
+#[cfg(feature = "gui")]
+pub mod gui;
+pub mod history;
+#[cfg(feature = "gui")]
+pub mod labels;
+#[cfg(feature = "gui")]
+pub mod network;
+pub mod notation;
+pub mod player_setting;
+pub mod position;
+#[cfg(feature = "gui")]
+pub mod positions;
+pub mod record;
+#[cfg(feature = "gui")]
+pub mod settings;
+pub mod side;
+#[cfg(feature = "gui")]
+pub mod worker;
+#[cfg(feature = "gui")]
+mod editor;
+#[cfg(feature = "gui")]
+mod replay;
+
+// the engine and the match runner are built without the GUI by turning off the default features
+#[cfg(feature = "gui")]
+pub use board::AbaloneBoard;
+#[cfg(feature = "gui")]
+pub use controller::GameController;
+#[cfg(feature = "gui")]
+pub use gui::AbaloneGUI;
@@ -0,0 +1,409 @@
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::{thread, time};
+
+use crate::clock::TimeControl;
+use crate::history::MoveHistory;
+use crate::notation::find_move;
+use crate::position::parse_position;
+use crate::record::board_from_string;
+use crate::side::SidedGame;
+
+/// peers with another protocol version are rejected
+pub const PROTOCOL_VERSION: u32 = 1;
+/// port used if the address of a remote player has none
+pub const DEFAULT_PORT: u16 = 7777;
+/// port spectators connect to
+pub const SPECTATOR_PORT: u16 = 7778;
+/// longest message in bytes, a longer line closes the connection, games of thousands of moves still fit
+const MAX_LINE: usize = 1 << 20;
+/// a spectator who does not take a message in this time is dropped
+const SPECTATOR_TIMEOUT: time::Duration = time::Duration::from_secs(2);
+
+/// messages of the LAN protocol, every message is one line of JSON
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NetMessage {
+    /// first message of the joining instance
+    Hello { version: u32, name: String },
+    /// answer of the host, the joining instance continues this game
+    Setup {
+        version: u32,
+        name: String,
+        /// the host plays the blue marbles
+        host_is_black: bool,
+        /// starting position in the format of `format_position`
+        position: String,
+        /// states chosen by the movers so far in the format of `board_to_string`
+        moves: Vec<String>,
+        time_control: TimeControl,
+    },
+    /// the host does not accept the joining instance
+    Reject { reason: String },
+    /// first message of a spectator
+    Watch { version: u32 },
+    /// the watched game, sent to new spectators and whenever the game is started or rewound
+    Game {
+        version: u32,
+        black_name: String,
+        white_name: String,
+        /// starting position in the format of `format_position`
+        position: String,
+        /// states chosen by the movers so far in the format of `board_to_string`
+        moves: Vec<String>,
+    },
+    /// state chosen by the mover in the format of `board_to_string`, `ply` is the number of moves before it
+    Move { ply: usize, state: String },
+}
+
+/// news from the connection threads
+pub enum RemoteEvent {
+    /// the other instance connected, the stream is used for sending
+    Connected(TcpStream),
+    Message(NetMessage),
+    /// the connection ended or could not be established
+    Closed(String),
+}
+
+/// connection to the instance of the remote player, the messages are read in a thread
+pub struct RemoteLink {
+    /// this instance hosts the game
+    pub hosting: bool,
+    /// name of the local player sent to the other instance
+    pub local_name: String,
+    /// the joining instance has received the game of the host
+    pub ready: bool,
+    writer: Option<TcpStream>,
+    events: Receiver<RemoteEvent>,
+    /// stops waiting for a connection
+    stop: Arc<AtomicBool>,
+}
+
+/// adds the port if the address has none
+fn with_port(address: &str, port: u16) -> String {
+    if address.contains(':') {address.to_string()} else {format!("{}:{}", address, port)}
+}
+
+/// rebuilds a game sent over the network, every move is checked to be possible
+pub fn rebuild_game(position: &str, moves: &[String]) -> Result<(MoveHistory, SidedGame), String> {
+    let (board, black_starts) = parse_position(position).map_err(|err| err.to_string())?;
+    let mut history = MoveHistory::new(board, black_starts);
+    let mut game = MoveHistory::new_game(board, black_starts);
+    for (num, state) in moves.iter().enumerate() {
+        let state = board_from_string(state).map_err(|err| err.to_string())?;
+        if game.get_game_ended() || find_move(&game, state).is_none() {
+            return Err(format!("move {} is not possible", num + 1));
+        }
+        let black_moved = game.get_black_tomove();
+        MoveHistory::apply_state(&mut game, state, black_moved);
+        history.moves.push((state, black_moved));
+    }
+    Ok((history, game))
+}
+
+fn send_message(stream: &mut TcpStream, message: &NetMessage) -> std::io::Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())
+}
+
+/// reads the next line of at most `MAX_LINE` bytes, `None` at the end of the stream
+fn read_message_line(reader: &mut impl BufRead) -> std::io::Result<Option<String>> {
+    let mut line = String::new();
+    let len = reader.take(MAX_LINE as u64).read_line(&mut line)?;
+    if len == MAX_LINE && !line.ends_with('\n') {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("a message is longer than {} bytes", MAX_LINE)));
+    }
+    Ok((len > 0).then_some(line))
+}
+
+/// forwards the messages of the stream until it is closed
+fn read_messages(stream: TcpStream, events: Sender<RemoteEvent>, repaint_ctx: Option<egui::Context>) {
+    let send = |event: RemoteEvent| {
+        let sent = events.send(event).is_ok();
+        if let Some(ctx) = &repaint_ctx {
+            ctx.request_repaint();
+        }
+        sent
+    };
+    let mut reader = BufReader::new(stream);
+    loop {
+        let event = match read_message_line(&mut reader) {
+            Ok(Some(line)) => match serde_json::from_str(&line) {
+                Ok(message) => RemoteEvent::Message(message),
+                Err(err) => RemoteEvent::Closed(format!("invalid message of the remote player: {}", err)),
+            },
+            Ok(None) => RemoteEvent::Closed("the remote player left the game".to_string()),
+            Err(err) => RemoteEvent::Closed(format!("connection to the remote player lost: {}", err)),
+        };
+        let closed = matches!(event, RemoteEvent::Closed(_));
+        // the link is dropped on a closing event, which shuts the connection down
+        if !send(event) || closed {
+            return;
+        }
+    }
+}
+
+impl RemoteLink {
+    /// waits for the remote player in the background, fails if the address cannot be used
+    pub fn host(address: &str, local_name: &str, repaint_ctx: Option<egui::Context>) -> Result<Self, String> {
+        let address = with_port(address, DEFAULT_PORT);
+        let listener = TcpListener::bind(&address).map_err(|err| format!("cannot host on {}: {}", address, err))?;
+        // the listener is polled, so waiting can be stopped
+        listener.set_nonblocking(true).map_err(|err| err.to_string())?;
+        let (sender, events) = unbounded();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_flag = stop.clone();
+        thread::spawn(move || {
+            while !stop_flag.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        let _ = stream.set_nonblocking(false);
+                        match stream.try_clone() {
+                            Ok(writer) => {
+                                let _ = sender.send(RemoteEvent::Connected(writer));
+                                read_messages(stream, sender, repaint_ctx);
+                            }
+                            Err(err) => { let _ = sender.send(RemoteEvent::Closed(err.to_string())); }
+                        }
+                        return;
+                    }
+                    Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => thread::sleep(time::Duration::from_millis(100)),
+                    Err(err) => {
+                        let _ = sender.send(RemoteEvent::Closed(format!("cannot accept the remote player: {}", err)));
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(Self { hosting: true, local_name: local_name.to_string(), ready: true, writer: None, events, stop })
+    }
+
+    /// connects to the host in the background and introduces the local player
+    pub fn join(address: &str, local_name: &str, repaint_ctx: Option<egui::Context>) -> Self {
+        let hello = NetMessage::Hello { version: PROTOCOL_VERSION, name: local_name.to_string() };
+        Self::connect(with_port(address, DEFAULT_PORT), hello, local_name, repaint_ctx)
+    }
+
+    /// connects to a shared game as spectator in the background
+    pub fn watch(address: &str, repaint_ctx: Option<egui::Context>) -> Self {
+        let watch = NetMessage::Watch { version: PROTOCOL_VERSION };
+        Self::connect(with_port(address, SPECTATOR_PORT), watch, "", repaint_ctx)
+    }
+
+    fn connect(address: String, hello: NetMessage, local_name: &str, repaint_ctx: Option<egui::Context>) -> Self {
+        let (sender, events) = unbounded();
+        thread::spawn(move || {
+            let connected = TcpStream::connect(&address).and_then(|mut stream| {
+                send_message(&mut stream, &hello)?;
+                Ok((stream.try_clone()?, stream))
+            });
+            match connected {
+                Ok((writer, stream)) => {
+                    let _ = sender.send(RemoteEvent::Connected(writer));
+                    read_messages(stream, sender, repaint_ctx);
+                }
+                Err(err) => { let _ = sender.send(RemoteEvent::Closed(format!("cannot connect to {}: {}", address, err))); }
+            }
+        });
+        Self {
+            hosting: false,
+            local_name: local_name.to_string(),
+            ready: false,
+            writer: None,
+            events,
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    /// sends the message if the other instance is connected
+    pub fn send(&mut self, message: &NetMessage) -> Result<(), String> {
+        match self.writer.as_mut() {
+            Some(writer) => send_message(writer, message).map_err(|err| format!("cannot reach the remote player: {}", err)),
+            None => Ok(()),
+        }
+    }
+
+    /// the next message or the end of the connection, the stream of a new connection is kept for sending
+    pub fn try_recv(&mut self) -> Option<RemoteEvent> {
+        match self.events.try_recv().ok()? {
+            RemoteEvent::Connected(writer) => {
+                self.writer = Some(writer);
+                self.try_recv()
+            }
+            event => Some(event),
+        }
+    }
+}
+
+impl Drop for RemoteLink {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // ends the reading thread as well
+        if let Some(writer) = &self.writer {
+            let _ = writer.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+/// changes of the shared game, they are handled by the thread writing to the spectators
+enum AudienceEvent {
+    Game(NetMessage),
+    Move { ply: usize, state: String },
+    /// a spectator who was welcomed
+    Spectator(TcpStream),
+    Close,
+}
+
+/// the shared game and the spectators watching it
+#[derive(Default)]
+struct Audience {
+    /// the latest `Game` message, moves are added as they are played
+    game: Option<NetMessage>,
+    spectators: Vec<TcpStream>,
+}
+
+impl Audience {
+    /// sends the message to every spectator, spectators who cannot take it are dropped
+    fn broadcast(&mut self, message: &NetMessage) {
+        self.spectators.retain_mut(|stream| send_message(stream, message).is_ok());
+    }
+
+    /// writes to the spectators in its own thread until the server is closed, so slow spectators never hold up the GUI
+    fn run(mut self, events: Receiver<AudienceEvent>, num_spectators: Arc<AtomicUsize>) {
+        for event in events {
+            match event {
+                AudienceEvent::Game(game) => {
+                    self.broadcast(&game);
+                    self.game = Some(game);
+                }
+                AudienceEvent::Move { ply, state } => {
+                    if let Some(NetMessage::Game { moves, .. }) = self.game.as_mut() {
+                        moves.push(state.clone());
+                    }
+                    self.broadcast(&NetMessage::Move { ply, state });
+                }
+                AudienceEvent::Spectator(mut stream) => {
+                    if self.game.as_ref().is_none_or(|game| send_message(&mut stream, game).is_ok()) {
+                        self.spectators.push(stream);
+                    }
+                }
+                AudienceEvent::Close => {
+                    for stream in self.spectators.iter() {
+                        let _ = stream.shutdown(Shutdown::Both);
+                    }
+                    return;
+                }
+            }
+            num_spectators.store(self.spectators.len(), Ordering::Relaxed);
+        }
+    }
+}
+
+/// lets spectators watch the game read-only, they get the game and every move afterwards
+pub struct SpectatorServer {
+    events: Sender<AudienceEvent>,
+    num_spectators: Arc<AtomicUsize>,
+    /// stops accepting spectators
+    stop: Arc<AtomicBool>,
+}
+
+impl SpectatorServer {
+    /// accepts spectators on the port of every network interface in the background
+    pub fn start(port: u16) -> Result<Self, String> {
+        let listener = TcpListener::bind(("0.0.0.0", port)).map_err(|err| format!("cannot share on port {}: {}", port, err))?;
+        listener.set_nonblocking(true).map_err(|err| err.to_string())?;
+        let (events, audience_events) = unbounded();
+        let num_spectators = Arc::new(AtomicUsize::new(0));
+        let counter = num_spectators.clone();
+        thread::spawn(move || Audience::default().run(audience_events, counter));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (spectator_sender, stop_flag) = (events.clone(), stop.clone());
+        thread::spawn(move || {
+            while !stop_flag.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        let spectator_sender = spectator_sender.clone();
+                        // a slow spectator does not keep others from joining
+                        thread::spawn(move || Self::welcome(stream, spectator_sender));
+                    }
+                    Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => thread::sleep(time::Duration::from_millis(100)),
+                    Err(_) => return,
+                }
+            }
+        });
+        Ok(Self { events, num_spectators, stop })
+    }
+
+    /// checks the first message of the spectator, the current game is sent when the spectator is added
+    fn welcome(mut stream: TcpStream, spectator_sender: Sender<AudienceEvent>) {
+        let _ = stream.set_nonblocking(false);
+        let _ = stream.set_read_timeout(Some(SPECTATOR_TIMEOUT));
+        let _ = stream.set_write_timeout(Some(SPECTATOR_TIMEOUT));
+        let Ok(reader) = stream.try_clone() else {
+            return;
+        };
+        let Ok(Some(line)) = read_message_line(&mut BufReader::new(reader)) else {
+            return;
+        };
+        match serde_json::from_str(&line) {
+            Ok(NetMessage::Watch { version }) if version == PROTOCOL_VERSION => {
+                let _ = spectator_sender.send(AudienceEvent::Spectator(stream));
+            }
+            Ok(NetMessage::Watch { version: _ }) => {
+                let reason = format!("the game is shared with protocol version {}", PROTOCOL_VERSION);
+                let _ = send_message(&mut stream, &NetMessage::Reject { reason });
+            }
+            _ => {}
+        }
+    }
+
+    /// replaces the shared game, e.g. after it was started or rewound
+    pub fn set_game(&self, game: NetMessage) {
+        let _ = self.events.send(AudienceEvent::Game(game));
+    }
+
+    /// adds the move to the shared game and sends it to the spectators
+    pub fn push_move(&self, ply: usize, state: String) {
+        let _ = self.events.send(AudienceEvent::Move { ply, state });
+    }
+
+    pub fn num_spectators(&self) -> usize {
+        self.num_spectators.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for SpectatorServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.events.send(AudienceEvent::Close);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn limits_the_length_of_messages() {
+        let mut reader = Cursor::new("{\"type\":\"watch\",\"version\":1}\nlast");
+        assert_eq!(read_message_line(&mut reader).unwrap().as_deref(), Some("{\"type\":\"watch\",\"version\":1}\n"));
+        assert_eq!(read_message_line(&mut reader).unwrap().as_deref(), Some("last"));
+        assert!(read_message_line(&mut reader).unwrap().is_none());
+
+        let mut reader = Cursor::new("x".repeat(MAX_LINE - 1) + "\n");
+        assert_eq!(read_message_line(&mut reader).unwrap().map(|line| line.len()), Some(MAX_LINE));
+        let mut reader = Cursor::new("x".repeat(MAX_LINE) + "\n");
+        assert!(read_message_line(&mut reader).is_err());
+    }
+}
@@ -0,0 +1,255 @@
+use std::fmt;
+use rustai_abalone::game::{AbaloneGame, Board, Coord, MarbleMove};
+use crate::constants::{COL_OFFSETS, ROW_SIZES};
+use crate::side::SidedGame;
+
+// standard Abalone notation:
+// rows are named 'A' (bottom) to 'I' (top), diagonals are numbered 1 to 9
+// inline moves (including single marbles) consist of the trailing marble and its destination, e.g. "A1B2"
+// broadside moves consist of both ends of the line and the destination of the first one, e.g. "A1A3B2"
+
+#[derive(Debug)]
+pub enum NotationError {
+    Format(String),
+    Field(String),
+    Illegal(String),
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotationError::Format(msg) => write!(f, "invalid notation: {}", msg),
+            NotationError::Field(field) => write!(f, "'{}' is not a board field", field),
+            NotationError::Illegal(msg) => write!(f, "illegal move: {}", msg),
+        }
+    }
+}
+
+pub fn is_on_board(x: i32, y: i32) -> bool {
+    if !(1..=9).contains(&x) {
+        return false;
+    }
+    let offset = COL_OFFSETS[x as usize - 1] as i32;
+    y >= offset && y < offset + ROW_SIZES[x as usize - 1] as i32
+}
+
+pub fn coord_to_field(coord: Coord) -> String {
+    // rows are counted from the bottom in the notation
+    let row = (b'A' + (9 - coord.x) as u8) as char;
+    format!("{}{}", row, coord.y)
+}
+
+pub fn field_to_coord(field: &str) -> Result<Coord, NotationError> {
+    let mut chars = field.chars();
+    let (row, col) = match (chars.next(), chars.next(), chars.next()) {
+        (Some(row), Some(col), None) => (row.to_ascii_uppercase(), col),
+        _ => return Err(NotationError::Field(field.to_string())),
+    };
+    let x = 9 - (row as i32 - 'A' as i32);
+    let y = col.to_digit(10).map(|d| d as i32).unwrap_or(0);
+    if !row.is_ascii_alphabetic() || !is_on_board(x, y) {
+        return Err(NotationError::Field(field.to_string()));
+    }
+    Ok(Coord { x: x as usize, y: y as usize })
+}
+
+/// writes the move of the selected marbles in standard notation
+/// the move has to be possible in the current state of the game
+pub fn format_move(game: &SidedGame, selection: &[Coord], marb_move: MarbleMove) -> Result<String, NotationError> {
+    if !game.calc_coord_moves(selection.iter().copied().collect()).contains_key(&marb_move) {
+        return Err(NotationError::Illegal("the selected marbles cannot move in this direction".to_string()));
+    }
+    let mut line: Vec<Coord> = selection.to_vec();
+    line.sort_by_key(|coord| (coord.x, coord.y));
+    let (first, last) = (line[0], line[line.len() - 1]);
+    if line.len() == 1 {
+        return Ok(format!("{}{}", coord_to_field(first), coord_to_field(first + marb_move)));
+    }
+    let line_dir = line[1] - line[0];
+    if line_dir == marb_move {
+        Ok(format!("{}{}", coord_to_field(first), coord_to_field(first + marb_move)))
+    } else if (line_dir.dx as i32, line_dir.dy as i32) == (-(marb_move.dx as i32), -(marb_move.dy as i32)) {
+        Ok(format!("{}{}", coord_to_field(last), coord_to_field(last + marb_move)))
+    } else {
+        Ok(format!("{}{}{}", coord_to_field(first), coord_to_field(last), coord_to_field(first + marb_move)))
+    }
+}
+
+/// reads a move in standard notation and returns the moved marbles, the direction and the resulting state
+pub fn parse_move(game: &SidedGame, text: &str) -> Result<(Vec<Coord>, MarbleMove, Board), NotationError> {
+    let text = text.trim();
+    if !text.is_ascii() || (text.len() != 4 && text.len() != 6) {
+        return Err(NotationError::Format(format!("'{}' has to consist of two or three fields", text)));
+    }
+    let fields = (0..text.len()).step_by(2)
+        .map(|start| field_to_coord(&text[start..start + 2]))
+        .collect::<Result<Vec<Coord>, NotationError>>()?;
+    let (blacks, whites, _) = game.get_coords_by_type();
+    let own = if game.get_black_tomove() {blacks} else {whites};
+    if !own.contains(&fields[0]) {
+        return Err(NotationError::Illegal(format!("there is no own marble on {}", coord_to_field(fields[0]))));
+    }
+
+    let (selection, marb_move) = if fields.len() == 2 {
+        // inline: all own marbles in front of the trailing one are moved as well
+        let marb_move = neighbour_direction(fields[0], fields[1])
+            .ok_or_else(|| NotationError::Illegal(format!("{} is not next to {}", &text[2..4], &text[0..2])))?;
+        let mut selection = vec![fields[0]];
+        let mut next = fields[0] + marb_move;
+        while selection.len() < 3 && own.contains(&next) {
+            selection.push(next);
+            next = next + marb_move;
+        }
+        (selection, marb_move)
+    } else {
+        // broadside: the line between both ends is moved
+        let marb_move = neighbour_direction(fields[0], fields[2])
+            .ok_or_else(|| NotationError::Illegal(format!("{} is not next to {}", &text[4..6], &text[0..2])))?;
+        (line_between(fields[0], fields[1])?, marb_move)
+    };
+
+    match game.calc_coord_moves(selection.iter().copied().collect()).get(&marb_move) {
+        Some(next_state) => Ok((selection, marb_move, *next_state)),
+        None => Err(NotationError::Illegal(format!("'{}' is not possible in this position", text))),
+    }
+}
+
+/// lines of one to three own marbles the player to move can select, some lines appear in both directions
+pub fn selections(game: &SidedGame) -> Vec<Vec<Coord>> {
+    let (blacks, whites, _) = game.get_coords_by_type();
+    let own = if game.get_black_tomove() {blacks} else {whites};
+    let mut candidates = Vec::new();
+    for coord in own.iter() {
+        candidates.push(vec![*coord]);
+        for line_dir in AbaloneGame::get_game_moves() {
+            let second = *coord + line_dir;
+            if own.contains(&second) {
+                candidates.push(vec![*coord, second]);
+                if own.contains(&(second + line_dir)) {
+                    candidates.push(vec![*coord, second, second + line_dir]);
+                }
+            }
+        }
+    }
+    candidates
+}
+
+/// searches the marbles and direction that lead from the current state of the game to the given state
+pub fn find_move(game: &SidedGame, next_state: Board) -> Option<(Vec<Coord>, MarbleMove)> {
+    selections(game).into_iter().find_map(|selection| {
+        let moves = game.calc_coord_moves(selection.clone());
+        let marb_move = moves.iter().find(|(_, state)| **state == next_state).map(|(marb_move, _)| *marb_move)?;
+        Some((selection, marb_move))
+    })
+}
+
+/// formats the move leading from the current state of the game to the given state
+pub fn state_to_notation(game: &SidedGame, next_state: Board) -> Option<String> {
+    let (selection, marb_move) = find_move(game, next_state)?;
+    format_move(game, &selection, marb_move).ok()
+}
+
+fn neighbour_direction(from: Coord, to: Coord) -> Option<MarbleMove> {
+    AbaloneGame::get_game_moves().into_iter().find(|marb_move| from + *marb_move == to)
+}
+
+fn line_between(start: Coord, end: Coord) -> Result<Vec<Coord>, NotationError> {
+    let diff = end - start;
+    let (dx, dy) = (diff.dx as i32, diff.dy as i32);
+    // lines run along rows, along columns or along the remaining diagonal
+    let steps = dx.abs().max(dy.abs());
+    if steps == 0 || steps > 2 || !(dx == 0 || dy == 0 || dx == -dy) {
+        return Err(NotationError::Illegal(format!(
+            "{} and {} are not the ends of a line of two or three marbles", coord_to_field(start), coord_to_field(end))));
+    }
+    let step = MarbleMove { dx: (dx / steps) as _, dy: (dy / steps) as _ };
+    let mut line = vec![start];
+    for _ in 0..steps {
+        line.push(line[line.len() - 1] + step);
+    }
+    Ok(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustai_abalone::game::BELGIAN_DAISY;
+    use crate::record::board_coords;
+
+    /// every line of one to three fields on the board that starts at the coordinate
+    fn lines_from(coord: Coord) -> Vec<Vec<Coord>> {
+        let mut lines = vec![vec![coord]];
+        for line_dir in AbaloneGame::get_game_moves() {
+            let mut line = vec![coord];
+            for steps in 1..3 {
+                let (x, y) = (coord.x as i32 + steps * line_dir.dx as i32, coord.y as i32 + steps * line_dir.dy as i32);
+                if !is_on_board(x, y) {
+                    break;
+                }
+                line.push(Coord { x: x as usize, y: y as usize });
+                lines.push(line.clone());
+            }
+        }
+        lines
+    }
+
+    fn round_trip_all_moves(black_tomove: bool) {
+        let game = SidedGame::new(BELGIAN_DAISY, black_tomove);
+        let (mut inline, mut broadside) = (0, 0);
+        for selection in board_coords().into_iter().flat_map(lines_from) {
+            for (marb_move, next_state) in game.calc_coord_moves(selection.clone()) {
+                let text = format_move(&game, &selection, marb_move).unwrap();
+                let (_, parsed_move, parsed_state) = parse_move(&game, &text).unwrap();
+                assert_eq!((parsed_move, parsed_state), (marb_move, next_state), "{}", text);
+                // the notation of the state may name the line from its other end
+                let found_text = state_to_notation(&game, next_state).unwrap();
+                assert_eq!(parse_move(&game, &found_text).unwrap().2, next_state, "{}", found_text);
+                if text.len() == 4 {inline += 1} else {broadside += 1}
+            }
+        }
+        assert!(inline > 0 && broadside > 0);
+    }
+
+    #[test]
+    fn round_trip_blue_moves() {
+        round_trip_all_moves(true);
+    }
+
+    #[test]
+    fn round_trip_yellow_moves() {
+        round_trip_all_moves(false);
+    }
+
+    #[test]
+    fn formats_inline_and_broadside_moves() {
+        let game = SidedGame::new(BELGIAN_DAISY, true);
+        for text in ["C5D5", "A4B4", "C5C6D5"] {
+            let (selection, marb_move, _) = parse_move(&game, text).unwrap();
+            assert_eq!(format_move(&game, &selection, marb_move).unwrap(), text);
+        }
+        // the marbles in front of the trailing one move along
+        assert_eq!(parse_move(&game, "A4B4").unwrap().0.len(), 2);
+        let yellow_game = SidedGame::new(BELGIAN_DAISY, false);
+        assert_eq!(parse_move(&yellow_game, "a2b2").unwrap().0.len(), 3);
+    }
+
+    #[test]
+    fn rejects_illegal_moves() {
+        let game = SidedGame::new(BELGIAN_DAISY, true);
+        // a yellow marble, a push of one against one, fields that are no neighbours and a blocked broadside move
+        for text in ["A1B1", "B4B3", "C5A5", "B4B6C4", "C5C6D7"] {
+            assert!(matches!(parse_move(&game, text), Err(NotationError::Illegal(_))), "{}", text);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_moves() {
+        let game = SidedGame::new(BELGIAN_DAISY, true);
+        for text in ["", "C5", "C5D", "C5D5E", "C5D5E5F5", "Ä5D5"] {
+            assert!(matches!(parse_move(&game, text), Err(NotationError::Format(_))), "{}", text);
+        }
+        for text in ["Z1A1", "A6B6", "C0D5", "C5XX"] {
+            assert!(matches!(parse_move(&game, text), Err(NotationError::Field(_))), "{}", text);
+        }
+    }
+}
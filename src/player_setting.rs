@@ -1,10 +1,119 @@
-#[derive(PartialEq, Eq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum PlayerSetting {
     Human,
     MagisterLudiAI {
         mcts_num: usize,
         mcts_parallel: usize,
         mcts_minimum: usize,
-        mcts_depth: usize
+        mcts_depth: usize,
+        /// the AI keeps searching while its opponent thinks
+        #[serde(default)]
+        ponder: bool,
+    },
+    /// a player of another instance of the game, connected over TCP
+    Remote {
+        /// address to host the game on or to join
+        address: String,
+        /// this instance hosts the game and waits for the other one
+        host: bool,
+    },
+    /// plays random moves, the same ones in every game
+    Random,
+    /// plays the move ejecting the most marbles or keeping the marbles closest to the center
+    Greedy,
+    /// a program speaking the engine protocol, started for every game
+    ExternalEngine {
+        command: String,
+        args: Vec<String>,
+    },
+}
+impl PlayerSetting {
+    /// the Magister Ludi AI offered in the start window
+    pub fn default_ai() -> Self {
+        PlayerSetting::MagisterLudiAI { mcts_num: 200, mcts_parallel: 12, mcts_minimum: 7, mcts_depth: 0, ponder: false }
     }
-}
\ No newline at end of file
+
+    /// a remote player who joins the game hosted by this instance
+    pub fn default_host() -> Self {
+        PlayerSetting::Remote { address: "0.0.0.0".to_string(), host: true }
+    }
+
+    /// players of a game record can be taken over without starting programs or opening connections,
+    /// external engines and remote players only come from the own settings
+    pub fn is_safe_to_load(&self) -> bool {
+        !matches!(self, PlayerSetting::ExternalEngine { .. } | PlayerSetting::Remote { .. })
+    }
+
+    /// reads "human", "random", "greedy", "ai" for the default AI, the Magister Ludi parameters "NUM,PARALLEL,MINIMUM,DEPTH",
+    /// "host" for a remote player joining this instance, "join:ADDRESS" for a remote player hosting on the address
+    /// or "engine:COMMAND ARGS" for an external engine
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text.trim() {
+            "human" => return Ok(PlayerSetting::Human),
+            "random" => return Ok(PlayerSetting::Random),
+            "greedy" => return Ok(PlayerSetting::Greedy),
+            "ai" => return Ok(Self::default_ai()),
+            "host" => return Ok(Self::default_host()),
+            _ => {}
+        }
+        if let Some(address) = text.trim().strip_prefix("join:") {
+            return Ok(PlayerSetting::Remote { address: address.to_string(), host: false });
+        }
+        if let Some(command_line) = text.trim().strip_prefix("engine:") {
+            let mut parts = command_line.split_whitespace().map(str::to_string);
+            let command = parts.next().ok_or_else(|| "the engine needs a command".to_string())?;
+            return Ok(PlayerSetting::ExternalEngine { command, args: parts.collect() });
+        }
+        let values = text.split(',')
+            .map(|value| value.trim().parse::<usize>().map_err(|err| format!("'{}': {}", value, err)))
+            .collect::<Result<Vec<usize>, String>>()?;
+        match values[..] {
+            [mcts_num, mcts_parallel, mcts_minimum, mcts_depth] => Ok(PlayerSetting::MagisterLudiAI { mcts_num, mcts_parallel, mcts_minimum, mcts_depth, ponder: false }),
+            _ => Err(format!("'{}' has to contain four numbers", text)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_named_players() {
+        assert_eq!(PlayerSetting::parse("human"), Ok(PlayerSetting::Human));
+        assert_eq!(PlayerSetting::parse(" random "), Ok(PlayerSetting::Random));
+        assert_eq!(PlayerSetting::parse("greedy"), Ok(PlayerSetting::Greedy));
+        assert_eq!(PlayerSetting::parse("ai"), Ok(PlayerSetting::default_ai()));
+        assert_eq!(PlayerSetting::parse("host"), Ok(PlayerSetting::default_host()));
+        assert!(PlayerSetting::parse("Human").is_err());
+    }
+
+    #[test]
+    fn parses_magister_ludi_parameters() {
+        assert_eq!(
+            PlayerSetting::parse("400, 8,5,2"),
+            Ok(PlayerSetting::MagisterLudiAI { mcts_num: 400, mcts_parallel: 8, mcts_minimum: 5, mcts_depth: 2, ponder: false })
+        );
+        for text in ["400,8,5", "400,8,5,2,1", "400,8,five,2", "-1,8,5,2", ""] {
+            assert!(PlayerSetting::parse(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn parses_remote_players_and_engines() {
+        assert_eq!(
+            PlayerSetting::parse("join:192.168.1.20:7878"),
+            Ok(PlayerSetting::Remote { address: "192.168.1.20:7878".to_string(), host: false })
+        );
+        assert_eq!(
+            PlayerSetting::parse("engine:magister_engine --log  engine.log"),
+            Ok(PlayerSetting::ExternalEngine {
+                command: "magister_engine".to_string(),
+                args: vec!["--log".to_string(), "engine.log".to_string()],
+            })
+        );
+        assert!(PlayerSetting::parse("engine:  ").is_err());
+    }
+}
@@ -0,0 +1,192 @@
+use std::path::Path;
+use rustai_abalone::game::{AbaloneGame, Board, BELGIAN_DAISY, CLASSIC, GERMAN_DAISY};
+use serde::Deserialize;
+
+use crate::record::{board_from_string, board_to_string, RecordError};
+
+/// marbles of a player at the start of a game
+pub const START_MARBLES: usize = 14;
+
+/// a position new games can be started from
+#[derive(Clone, PartialEq)]
+pub struct StartingPosition {
+    /// shown in the start window
+    pub name: String,
+    pub board: Board,
+    /// the blue player has the first move
+    pub black_starts: bool,
+}
+
+impl StartingPosition {
+    pub fn new(name: &str, board: Board) -> Self {
+        Self { name: name.to_string(), board, black_starts: true }
+    }
+
+    /// the positions compiled into the game
+    pub fn builtin() -> Vec<Self> {
+        vec![
+            Self::new("Belgian Daisy", BELGIAN_DAISY),
+            Self::new("German Daisy", GERMAN_DAISY),
+            Self::new("Classic", CLASSIC),
+        ]
+    }
+}
+
+/// content of a position library file
+#[derive(Deserialize)]
+struct PositionLibrary {
+    #[serde(default)]
+    position: Vec<LibraryEntry>,
+}
+
+#[derive(Deserialize)]
+struct LibraryEntry {
+    name: String,
+    /// board in the format of `board_to_string`
+    board: String,
+    #[serde(default = "default_black_starts")]
+    black_starts: bool,
+}
+
+/// positions of a library start with blue unless they say otherwise
+fn default_black_starts() -> bool {
+    true
+}
+
+/// reads the named positions of a TOML position library
+pub fn load_library(path: &Path) -> Result<Vec<StartingPosition>, RecordError> {
+    let content = std::fs::read_to_string(path)?;
+    let library: PositionLibrary = toml::from_str(&content).map_err(|err| RecordError::Library(err.to_string()))?;
+    library.position.into_iter().map(|entry| {
+        let board = board_from_string(&entry.board)
+            .map_err(|err| RecordError::Library(format!("position '{}' has an {}", entry.name, err)))?;
+        Ok(StartingPosition { name: entry.name, board, black_starts: entry.black_starts })
+    }).collect()
+}
+
+/// adds the positions to the list, a position with the name of an existing one replaces it
+pub fn merge_positions(positions: &mut Vec<StartingPosition>, new_positions: Vec<StartingPosition>) {
+    for new_position in new_positions {
+        match positions.iter_mut().find(|position| position.name == new_position.name) {
+            Some(position) => *position = new_position,
+            None => positions.push(new_position),
+        }
+    }
+}
+
+/// writes a compact position string like "bb1yy/bbbyyy/1bb1yy1/8/9/8/1yy1bb1/yyybbb/yy1bb b 0 0"
+/// the rows go from top to bottom with 'b' for blue and 'y' for yellow marbles and numbers for empty fields,
+/// followed by the player to move and the marbles lost by the blue and the yellow player
+pub fn format_position(board: Board, black_tomove: bool) -> String {
+    let (blacks, whites, _) = AbaloneGame::coords_by_type(board);
+    let mut rows = String::with_capacity(40);
+    for row in board_to_string(board).split('/') {
+        if !rows.is_empty() {
+            rows.push('/');
+        }
+        let mut empties = 0;
+        for field in row.chars() {
+            if field == '.' {
+                empties += 1;
+                continue;
+            }
+            if empties > 0 {
+                rows.push_str(&empties.to_string());
+                empties = 0;
+            }
+            rows.push(field);
+        }
+        if empties > 0 {
+            rows.push_str(&empties.to_string());
+        }
+    }
+    format!(
+        "{} {} {} {}",
+        rows,
+        if black_tomove {'b'} else {'y'},
+        START_MARBLES.saturating_sub(blacks.len()),
+        START_MARBLES.saturating_sub(whites.len())
+    )
+}
+
+/// reads a position string written by `format_position`, returns the board and whether blue is to move
+/// the lost marbles have to fit to the marbles on the board
+pub fn parse_position(text: &str) -> Result<(Board, bool), RecordError> {
+    let parts: Vec<&str> = text.split_whitespace().collect();
+    if parts.len() != 4 {
+        return Err(RecordError::Board(format!("'{}' has to contain the rows, the player to move and the lost marbles", text)));
+    }
+    // numbers stand for as many empty fields
+    let mut rows = String::with_capacity(69);
+    for field in parts[0].chars() {
+        match field.to_digit(10) {
+            Some(empties) => rows.extend(std::iter::repeat_n('.', empties as usize)),
+            None => rows.push(field),
+        }
+    }
+    let board = board_from_string(&rows)?;
+    let black_tomove = match parts[1] {
+        "b" => true,
+        "y" => false,
+        other => return Err(RecordError::Board(format!("unknown player to move '{}'", other))),
+    };
+    let (blacks, whites, _) = AbaloneGame::coords_by_type(board);
+    for (name, marbles, loss) in [("blue", blacks.len(), parts[2]), ("yellow", whites.len(), parts[3])] {
+        let loss: usize = loss.parse().map_err(|_| RecordError::Board(format!("'{}' is no number of lost marbles", loss)))?;
+        if marbles + loss != START_MARBLES {
+            return Err(RecordError::Board(format!("the {} player has {} marbles left but lost {}", name, marbles, loss)));
+        }
+    }
+    Ok((board, black_tomove))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAISY: &str = "bb1yy/bbbyyy/1bb1yy1/8/9/8/1yy1bb1/yyybbb/yy1bb";
+
+    #[test]
+    fn builtin_positions_round_trip() {
+        for position in StartingPosition::builtin() {
+            for black_tomove in [true, false] {
+                let text = format_position(position.board, black_tomove);
+                let (board, parsed_black_tomove) = parse_position(&text).unwrap();
+                assert!(board == position.board && parsed_black_tomove == black_tomove, "{}", text);
+            }
+        }
+        assert_eq!(format_position(BELGIAN_DAISY, true), format!("{} b 0 0", DAISY));
+    }
+
+    #[test]
+    fn rejects_invalid_positions() {
+        let invalid = [
+            // a row that is one field short
+            "bb1yy/bbbyyy/1bb1yy/8/9/8/1yy1bb1/yyybbb/yy1bb b 0 0",
+            // eight rows
+            "bb1yy/bbbyyy/1bb1yy1/8/9/8/1yy1bb1/yyybbb y 0 0",
+            "bb1yy/bbbyyy/1bb1yy1/8/9/8/1yy1bb1/yyybbb/yy1bx b 0 0",
+            // the player to move
+            "bb1yy/bbbyyy/1bb1yy1/8/9/8/1yy1bb1/yyybbb/yy1bb x 0 0",
+            "bb1yy/bbbyyy/1bb1yy1/8/9/8/1yy1bb1/yyybbb/yy1bb b 0",
+            // fifteen blue marbles
+            "bb1yy/bbbyyy/1bb1yy1/b7/9/8/1yy1bb1/yyybbb/yy1bb b 0 0",
+            // the lost marbles do not fit the board
+            "bb1yy/bbbyyy/1bb1yy1/8/9/8/1yy1bb1/yyybbb/yy1bb b 1 0",
+            "bb1yy/bbbyyy/1bb1yy1/8/9/8/1yy1bb1/yyybbb/yy1bb b 0 many",
+        ];
+        for text in invalid {
+            assert!(matches!(parse_position(text), Err(RecordError::Board(_))), "{}", text);
+        }
+    }
+
+    #[test]
+    fn merged_positions_replace_by_name() {
+        let mut positions = StartingPosition::builtin();
+        let yellow_classic = StartingPosition { black_starts: false, ..StartingPosition::new("Classic", CLASSIC) };
+        merge_positions(&mut positions, vec![yellow_classic.clone(), StartingPosition::new("Daisy", BELGIAN_DAISY)]);
+        let names: Vec<&str> = positions.iter().map(|position| position.name.as_str()).collect();
+        assert_eq!(names, ["Belgian Daisy", "German Daisy", "Classic", "Daisy"]);
+        assert!(positions[2] == yellow_classic);
+    }
+}
@@ -1,10 +1,13 @@
 use std::collections::HashSet;
 use rustai_abalone::game::{Coord, MarbleMove};
-use epaint::{pos2, Pos2, vec2, Vec2};
-use crate::constants::{BASE_WIDTH, BASE_HEIGHT, MARBLE_SIZE, DIST_SIZE, VEC_LEN};
+use epaint::{pos2, Pos2, vec2, Vec2, Rect};
+use epaint::emath::TSTransform;
+use crate::constants::{BASE_WIDTH, BASE_HEIGHT, MARBLE_SIZE, DIST_SIZE, VEC_LEN, BOARD_WIDTH, BOARD_HEIGHT};
 use eframe::egui::Shape;
 
 pub struct AbalonePositions {
+    /// maps the board layout (BOARD_WIDTH x BOARD_HEIGHT) to the screen, all positions below are already mapped
+    pub geometry: TSTransform,
     /// skull positions
     pub skull_pos: Vec<Pos2>,
     /// black marble positions
@@ -29,6 +32,8 @@ pub struct AbalonePositions {
     pub circle_move_empty: Vec<Shape>,
     /// vector containg the moves and the correspoding button positions
     pub move_pos:  Vec<(MarbleMove, Pos2, Vec2)>,
+    /// move button suggested by a hint
+    pub hint_move: Option<MarbleMove>,
 }
 
 impl Default for AbalonePositions {
@@ -66,6 +71,7 @@ impl Default for AbalonePositions {
             (MarbleMove { dx: 1, dy: 0 }, pos2(first_x+1.5*DIST_SIZE, first_y+2.0*MARBLE_SIZE), vec2(0.5*VEC_LEN, sin60*VEC_LEN))
         ];
         Self {
+            geometry: TSTransform::IDENTITY,
             skull_pos: Vec::with_capacity(12),
             black_deads,
             black_died: Vec::with_capacity(6),
@@ -78,6 +84,40 @@ impl Default for AbalonePositions {
             circle_selection: Vec::with_capacity(6),
             circle_move_empty: Vec::with_capacity(60),
             move_pos: button_positions,
+            hint_move: None,
         }
     }
-}
\ No newline at end of file
+}
+
+impl AbalonePositions {
+    /// largest geometry that fits the board layout into the given rect, the board is centered horizontally
+    pub fn fit_geometry(rect: Rect) -> TSTransform {
+        let scaling = (rect.width() / BOARD_WIDTH).min(rect.height() / BOARD_HEIGHT).max(0.1);
+        let offset = vec2(0.5 * (rect.width() - scaling * BOARD_WIDTH), 0.0);
+        TSTransform::new(rect.min.to_vec2() + offset, scaling)
+    }
+
+    /// moves and scales all positions and shapes from the current to the given geometry
+    pub fn set_geometry(&mut self, geometry: TSTransform) {
+        if geometry == self.geometry {
+            return;
+        }
+        let change = geometry * self.geometry.inverse();
+        for positions in [&mut self.skull_pos, &mut self.black_deads, &mut self.black_died, &mut self.white_deads, &mut self.white_died] {
+            for position in positions.iter_mut() {
+                *position = change * *position;
+            }
+        }
+        for (_, position) in self.black_pos.iter_mut().chain(self.white_pos.iter_mut()) {
+            *position = change * *position;
+        }
+        for shape in self.circle_selection.iter_mut().chain(self.circle_move_empty.iter_mut()) {
+            shape.transform(change);
+        }
+        for (_, position, direction) in self.move_pos.iter_mut() {
+            *position = change * *position;
+            *direction *= change.scaling;
+        }
+        self.geometry = geometry;
+    }
+}
@@ -0,0 +1,239 @@
+use std::fmt;
+use std::path::Path;
+use rustai_abalone::game::{AbaloneGame, Board, Coord, BELGIAN_DAISY, EMPTY_BOARD};
+use serde::{Deserialize, Serialize};
+use crate::clock::TimeControl;
+use crate::constants::{COL_OFFSETS, ROW_SIZES};
+use crate::history::MoveHistory;
+use crate::notation::{find_move, state_to_notation};
+use crate::player_setting::PlayerSetting;
+use crate::position::format_position;
+
+#[derive(Debug)]
+pub enum RecordError {
+    Io(std::io::Error),
+    Format(serde_json::Error),
+    Board(String),
+    Library(String),
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::Io(err) => write!(f, "could not access file: {}", err),
+            RecordError::Format(err) => write!(f, "invalid game record: {}", err),
+            RecordError::Board(msg) => write!(f, "invalid board: {}", msg),
+            RecordError::Library(msg) => write!(f, "invalid position library: {}", msg),
+        }
+    }
+}
+
+impl From<std::io::Error> for RecordError {
+    fn from(err: std::io::Error) -> Self {
+        RecordError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for RecordError {
+    fn from(err: serde_json::Error) -> Self {
+        RecordError::Format(err)
+    }
+}
+
+/// everything needed to resume or share a game
+#[derive(Serialize, Deserialize)]
+pub struct GameRecord {
+    pub black_name: String,
+    pub white_name: String,
+    pub black_ai: PlayerSetting,
+    pub white_ai: PlayerSetting,
+    #[serde(default)]
+    pub time_control: TimeControl,
+    /// set if a player lost on time, true for the blue player
+    #[serde(default)]
+    pub lost_on_time: Option<bool>,
+    /// starting board in the format of `board_to_string`
+    pub start: String,
+    /// the blue player made the first move
+    pub black_starts: bool,
+    /// all performed moves in playing order
+    pub moves: Vec<RecordedMove>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RecordedMove {
+    /// true if the blue player made the move
+    pub black_moved: bool,
+    /// canonical state after the move in the format of `board_to_string`,
+    /// which does not depend on the player who moved
+    pub state: String,
+    /// the move in standard notation, only written for readers of the file
+    #[serde(default)]
+    pub notation: String,
+}
+
+impl GameRecord {
+    pub fn new(
+        history: &MoveHistory, black_name: &str, white_name: &str, black_ai: &PlayerSetting, white_ai: &PlayerSetting,
+        time_control: &TimeControl, lost_on_time: Option<bool>
+    ) -> Self {
+        let mut game = MoveHistory::new_game(history.start_state, history.black_starts);
+        Self {
+            black_name: black_name.to_string(),
+            white_name: white_name.to_string(),
+            black_ai: black_ai.clone(),
+            white_ai: white_ai.clone(),
+            time_control: time_control.clone(),
+            lost_on_time,
+            start: board_to_string(history.start_state),
+            black_starts: history.black_starts,
+            moves: history.moves.iter().map(|(state, black_moved)| {
+                let notation = state_to_notation(&game, *state).unwrap_or_default();
+                MoveHistory::apply_state(&mut game, *state, *black_moved);
+                RecordedMove {
+                    black_moved: *black_moved,
+                    state: board_to_string(canonical_state(*state, *black_moved)),
+                    notation,
+                }
+            }).collect(),
+        }
+    }
+
+    /// converts the record back to a move history, every move is checked to be possible
+    pub fn to_history(&self) -> Result<MoveHistory, RecordError> {
+        let mut history = MoveHistory::new(board_from_string(&self.start)?, self.black_starts);
+        let mut game = MoveHistory::new_game(history.start_state, history.black_starts);
+        for (num, recorded) in self.moves.iter().enumerate() {
+            // the players alternate after the first move
+            if recorded.black_moved != ((num % 2 == 0) == self.black_starts) {
+                return Err(RecordError::Board(format!("move {} was made by the wrong player", num + 1)));
+            }
+            // the history keeps the states chosen by the players
+            let state = canonical_state(board_from_string(&recorded.state)?, recorded.black_moved);
+            if game.get_game_ended() || find_move(&game, state).is_none() {
+                return Err(RecordError::Board(format!(
+                    "move {} is not possible in {}", num + 1, format_position(game.get_state(), game.get_black_tomove())
+                )));
+            }
+            MoveHistory::apply_state(&mut game, state, recorded.black_moved);
+            history.moves.push((state, recorded.black_moved));
+        }
+        Ok(history)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), RecordError> {
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, RecordError> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
+/// the board is turned for the states chosen by the blue player, turning them again gives the canonical state
+/// and the other way round
+fn canonical_state(state: Board, black_moved: bool) -> Board {
+    if black_moved {AbaloneGame::rotate_board(state)} else {state}
+}
+
+/// all board coordinates row by row from top to bottom
+pub fn board_coords() -> Vec<Coord> {
+    let mut coords = Vec::with_capacity(61);
+    for (row, (offset, size)) in COL_OFFSETS.iter().zip(ROW_SIZES.iter()).enumerate() {
+        for col in *offset..*offset + *size {
+            coords.push(Coord { x: row + 1, y: col });
+        }
+    }
+    coords
+}
+
+/// writes the board row by row from top to bottom, rows are separated by '/'
+/// blue marbles are 'b', yellow marbles are 'y' and empty fields are '.'
+pub fn board_to_string(board: Board) -> String {
+    let (blacks, whites, _) = AbaloneGame::coords_by_type(board);
+    let mut text = String::with_capacity(69);
+    let mut last_row = 1;
+    for coord in board_coords() {
+        if coord.x != last_row {
+            text.push('/');
+            last_row = coord.x;
+        }
+        if blacks.contains(&coord) {
+            text.push('b');
+        } else if whites.contains(&coord) {
+            text.push('y');
+        } else {
+            text.push('.');
+        }
+    }
+    text
+}
+
+/// board value of a blue marble, taken from the top left field of the Belgian Daisy
+pub const BLACK_VALUE: i8 = BELGIAN_DAISY[1][5];
+/// board value of a yellow marble, taken from the fourth field of the top row of the Belgian Daisy
+pub const WHITE_VALUE: i8 = BELGIAN_DAISY[1][8];
+
+/// reads a board written by `board_to_string`
+pub fn board_from_string(text: &str) -> Result<Board, RecordError> {
+    let rows: Vec<&str> = text.trim().split('/').collect();
+    if rows.len() != ROW_SIZES.len() {
+        return Err(RecordError::Board(format!("expected {} rows but found {}", ROW_SIZES.len(), rows.len())));
+    }
+    let mut board = EMPTY_BOARD;
+    for (row, row_text) in rows.iter().enumerate() {
+        if row_text.chars().count() != ROW_SIZES[row] {
+            return Err(RecordError::Board(format!("row {} has to contain {} fields", row + 1, ROW_SIZES[row])));
+        }
+        for (num, field) in row_text.chars().enumerate() {
+            let (x, y) = (row + 1, COL_OFFSETS[row] + num);
+            match field {
+                'b' => board[x][y] = BLACK_VALUE,
+                'y' => board[x][y] = WHITE_VALUE,
+                '.' => {},
+                _ => return Err(RecordError::Board(format!("unknown field '{}' in row {}", field, row + 1))),
+            }
+        }
+    }
+    Ok(board)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::baseline::greedy_move;
+
+    fn round_trip(black_starts: bool) {
+        let mut history = MoveHistory::new(BELGIAN_DAISY, black_starts);
+        let mut game = MoveHistory::new_game(BELGIAN_DAISY, black_starts);
+        for _ in 0..6 {
+            let black_moved = game.get_black_tomove();
+            let next_state = greedy_move(&game).expect("the game is not over after a few moves");
+            MoveHistory::apply_state(&mut game, next_state, black_moved);
+            history.push(next_state, black_moved);
+        }
+        let record = GameRecord::new(
+            &history, "Blue", "Yellow", &PlayerSetting::Human, &PlayerSetting::Greedy, &TimeControl::default(), None
+        );
+        assert!(record.moves.iter().all(|recorded| !recorded.notation.is_empty()));
+
+        let json = serde_json::to_string(&record).unwrap();
+        let loaded = serde_json::from_str::<GameRecord>(&json).unwrap().to_history().unwrap();
+        assert_eq!(loaded.black_starts, black_starts);
+        assert_eq!(loaded.moves, history.moves);
+        assert_eq!(loaded.replay_game(history.moves.len()).get_state(), game.get_state());
+    }
+
+    #[test]
+    fn round_trip_blue_starts() {
+        round_trip(true);
+    }
+
+    #[test]
+    fn round_trip_yellow_starts() {
+        round_trip(false);
+    }
+}
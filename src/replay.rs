@@ -0,0 +1,21 @@
+pub struct ReplaySettings {
+    /// number of moves currently shown
+    pub ply: usize,
+    /// moves are performed automatically
+    pub playing: bool,
+    /// seconds between two automatic moves
+    pub speed: f64,
+    /// time of the last automatic move
+    pub last_step: f64,
+}
+
+impl Default for ReplaySettings {
+    fn default() -> Self {
+        Self {
+            ply: 0,
+            playing: false,
+            speed: 1.0,
+            last_step: 0.0,
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+use crate::clock::TimeControl;
+use crate::player_setting::PlayerSetting;
+
+/// key of the settings in the eframe storage
+pub const STORAGE_KEY: &str = "play_abalone_settings";
+
+/// choices of the start window that are restored on the next launch
+/// the window size is stored by eframe itself
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserSettings {
+    pub black_ai: PlayerSetting,
+    pub white_ai: PlayerSetting,
+    pub black_name: String,
+    pub white_name: String,
+    pub time_control: TimeControl,
+    /// the selected starting position is stored by name, as the position library may change
+    pub position_name: String,
+    pub dark_theme: bool,
+}
+
+impl Default for UserSettings {
+    fn default() -> Self {
+        Self {
+            black_ai: PlayerSetting::Human,
+            white_ai: PlayerSetting::Human,
+            black_name: "Blue Player".to_string(),
+            white_name: "Yellow Player".to_string(),
+            time_control: TimeControl::Unlimited,
+            position_name: "".to_string(),
+            dark_theme: true,
+        }
+    }
+}
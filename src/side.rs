@@ -0,0 +1,216 @@
+//! games and Magister Ludi AIs for positions with either side to move
+//!
+//! `AbaloneGame` and `MagisterLudi` always start with the blue player to move. A position with yellow
+//! to move is mirrored for them: the board is turned and the colors are swapped, which gives the same
+//! position with blue to move. States, coordinates and moves are mirrored back on the way out.
+
+use rustai_abalone::game::{AbaloneGame, Board, Coord, MarbleMove, EMPTY_BOARD};
+use rustai_abalone::player::MagisterLudi;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::record::{BLACK_VALUE, WHITE_VALUE};
+
+/// field in the middle of the board, it stays in place when the board is turned
+const CENTER: Coord = Coord { x: 5, y: 5 };
+/// simulations of the first search with a time limit, used to measure the speed of the AI
+const CALIBRATION_SIMULATIONS: usize = 100;
+/// the AI never performs less simulations, even if it is short of time
+const MIN_SIMULATIONS: usize = 20;
+
+/// exchanges the blue and the yellow marbles
+fn swap_colors(state: Board) -> Board {
+    state.map(|row| row.map(|field| match field {
+        BLACK_VALUE => WHITE_VALUE,
+        WHITE_VALUE => BLACK_VALUE,
+        field => field,
+    }))
+}
+
+/// the same position for the other player, mirroring twice gives the original state
+fn mirror(state: Board) -> Board {
+    AbaloneGame::rotate_board(swap_colors(state))
+}
+
+/// the field a marble is moved to when the board is turned
+fn mirror_coord(coord: Coord) -> Coord {
+    let mut board = EMPTY_BOARD;
+    board[coord.x][coord.y] = BLACK_VALUE;
+    let (blacks, _, _) = AbaloneGame::coords_by_type(AbaloneGame::rotate_board(board));
+    blacks[0]
+}
+
+fn mirror_move(marb_move: MarbleMove) -> MarbleMove {
+    mirror_coord(CENTER + marb_move) - mirror_coord(CENTER)
+}
+
+/// a game that may start with either player to move, it offers the methods of `AbaloneGame`
+/// with the same frames: states are canonical, except the chosen states of the blue player, which are turned
+#[derive(Clone)]
+pub struct SidedGame {
+    game: AbaloneGame,
+    /// the game started with yellow to move, so `game` holds the mirrored position
+    mirrored: bool,
+}
+
+impl SidedGame {
+    pub fn new(state: Board, black_tomove: bool) -> Self {
+        let mirrored = !black_tomove;
+        Self { game: AbaloneGame::new(if mirrored {mirror(state)} else {state}), mirrored }
+    }
+
+    pub fn get_state(&self) -> Board {
+        if self.mirrored {mirror(self.game.get_state())} else {self.game.get_state()}
+    }
+
+    pub fn get_black_tomove(&self) -> bool {
+        self.game.get_black_tomove() != self.mirrored
+    }
+
+    /// takes the canonical state after the move
+    pub fn update_state(&mut self, state: Board) {
+        self.game.update_state(if self.mirrored {mirror(state)} else {state});
+    }
+
+    pub fn get_game_ended(&self) -> bool {
+        self.game.get_game_ended()
+    }
+
+    /// -1 if blue won, 1 if yellow won
+    pub fn get_game_result(&self) -> i8 {
+        match self.game.get_game_result() {
+            result @ (-1 | 1) if self.mirrored => -result,
+            result => result,
+        }
+    }
+
+    pub fn get_black_white_loss(&self) -> (u8, u8) {
+        let (black_loss, white_loss) = self.game.get_black_white_loss();
+        if self.mirrored {(white_loss, black_loss)} else {(black_loss, white_loss)}
+    }
+
+    pub fn get_coords_by_type(&self) -> (Vec<Coord>, Vec<Coord>, Vec<Coord>) {
+        AbaloneGame::coords_by_type(self.get_state())
+    }
+
+    pub fn calc_coord_moves(&self, selection: Vec<Coord>) -> HashMap<MarbleMove, Board> {
+        if !self.mirrored {
+            return self.game.calc_coord_moves(selection);
+        }
+        // the turned states of the mirrored game only need their colors swapped
+        let selection = selection.into_iter().map(mirror_coord).collect();
+        self.game.calc_coord_moves(selection).into_iter()
+            .map(|(marb_move, next_state)| (mirror_move(marb_move), swap_colors(next_state)))
+            .collect()
+    }
+
+    pub fn differences_to_state(&self, next_state: Board) -> Vec<Coord> {
+        if !self.mirrored {
+            return self.game.differences_to_state(next_state);
+        }
+        self.game.differences_to_state(swap_colors(next_state)).into_iter().map(mirror_coord).collect()
+    }
+}
+
+/// a Magister Ludi AI that may start with either player to move, its states are in the frames of `SidedGame`
+pub struct SidedMagister {
+    magister: MagisterLudi,
+    /// the AI was created with yellow to move, so it plays the mirrored game
+    mirrored: bool,
+}
+
+impl SidedMagister {
+    pub fn new(
+        state: Board, black_tomove: bool, mcts_num: usize, mcts_parallel: usize, mcts_minimum: usize, mcts_depth: usize
+    ) -> Self {
+        let mirrored = !black_tomove;
+        let state = if mirrored {mirror(state)} else {state};
+        Self { magister: MagisterLudi::new(state, None, mcts_num, mcts_parallel, mcts_minimum, mcts_depth), mirrored }
+    }
+
+    /// searches the move of the player to move, the state is chosen like in `calc_coord_moves`
+    pub fn own_move(&mut self) -> Board {
+        let chosen_state = self.magister.own_move(false);
+        if self.mirrored {swap_colors(chosen_state)} else {chosen_state}
+    }
+
+    /// takes the canonical state after the move of the opponent
+    pub fn external_move(&mut self, state: Board) {
+        self.magister.external_move(if self.mirrored {mirror(state)} else {state}, true);
+    }
+
+    pub fn stop_execution(&mut self) {
+        self.magister.stop_execution();
+    }
+}
+
+/// simulations of an AI with at most `mcts_num` simulations that fit into the budget,
+/// `simulation_time` is the thinking time per simulation measured in an earlier search
+pub fn fit_simulations(mcts_num: usize, simulation_time: Option<f64>, budget: Duration) -> usize {
+    let affordable = match simulation_time {
+        Some(simulation_time) => (budget.as_secs_f64() / simulation_time) as usize,
+        None => CALIBRATION_SIMULATIONS,
+    };
+    affordable.clamp(MIN_SIMULATIONS.min(mcts_num), mcts_num)
+}
+
+/// the search tree is lost with a new instance, so an AI is only created again for a large change of its simulations
+pub fn needs_new_instance(current_num: usize, mcts_num: usize) -> bool {
+    mcts_num.abs_diff(current_num) * 10 > current_num
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustai_abalone::game::BELGIAN_DAISY;
+    use crate::history::MoveHistory;
+    use crate::notation::find_move;
+
+    #[test]
+    fn mirror_is_its_own_inverse() {
+        assert_eq!(mirror(mirror(BELGIAN_DAISY)), BELGIAN_DAISY);
+        for marb_move in AbaloneGame::get_game_moves() {
+            assert_eq!(mirror_move(mirror_move(marb_move)), marb_move);
+        }
+    }
+
+    #[test]
+    fn fits_simulations_to_the_budget() {
+        let budget = Duration::from_secs(2);
+        assert_eq!(fit_simulations(400, None, budget), CALIBRATION_SIMULATIONS);
+        assert_eq!(fit_simulations(50, None, budget), 50);
+        assert_eq!(fit_simulations(400, Some(0.01), budget), 200);
+        assert_eq!(fit_simulations(400, Some(0.001), budget), 400);
+        assert_eq!(fit_simulations(400, Some(1.0), budget), MIN_SIMULATIONS);
+        assert_eq!(fit_simulations(10, Some(1.0), budget), 10);
+        assert!(!needs_new_instance(200, 190));
+        assert!(needs_new_instance(200, 150));
+    }
+
+    #[test]
+    fn yellow_starts_without_a_pass() {
+        let mut game = SidedGame::new(BELGIAN_DAISY, false);
+        assert!(!game.get_black_tomove());
+        assert_eq!(game.get_state(), BELGIAN_DAISY);
+        let (_, whites, _) = game.get_coords_by_type();
+        let moves = game.calc_coord_moves(vec![whites[0]]);
+        let next_state = *moves.values().next().expect("a yellow marble of the start can move");
+        assert_eq!(game.differences_to_state(next_state).len(), 2);
+        MoveHistory::apply_state(&mut game, next_state, false);
+        assert!(game.get_black_tomove());
+        assert_eq!(game.get_state(), next_state);
+        assert_eq!(game.get_black_white_loss(), (0, 0));
+    }
+
+    #[test]
+    fn yellow_to_move_search_plays_yellow() {
+        let game = SidedGame::new(BELGIAN_DAISY, false);
+        let mut magister = SidedMagister::new(BELGIAN_DAISY, false, 20, 1, 7, 0);
+        let chosen_state = magister.own_move();
+        magister.stop_execution();
+        // the move is only found among the moves of the player to move
+        assert!(find_move(&game, chosen_state).is_some());
+        let (blacks, _, _) = AbaloneGame::coords_by_type(chosen_state);
+        assert_eq!(blacks, game.get_coords_by_type().0);
+    }
+}
@@ -0,0 +1,566 @@
+use rustai_abalone::game::{AbaloneGame, Board, EMPTY_BOARD};
+use crossbeam_channel::{select, unbounded, Receiver, Sender};
+use eframe::egui;
+use std::cell::RefCell;
+use std::{thread, time};
+
+use crate::analysis::SearchInfo;
+use crate::baseline::{greedy_move, RandomPlayer};
+use crate::clock::{GameClock, TimeControl};
+use crate::engine::{EngineProcess, EngineStop};
+use crate::history::MoveHistory;
+use crate::player_setting::PlayerSetting;
+use crate::side::{fit_simulations, needs_new_instance, SidedMagister};
+
+/// commands from the GUI to the worker thread
+pub enum WorkerCommand {
+    /// starts a game from the state, the players are created from the settings
+    NewGame {
+        game_id: u64,
+        state: Board,
+        black_tomove: bool,
+        black_ai: PlayerSetting,
+        white_ai: PlayerSetting,
+        clock: GameClock,
+    },
+    /// a move was made, the state is the one of the game afterwards
+    MovePlayed { state: Board, game_ended: bool },
+    /// the game was rewound to the state, the players are created again with their settings
+    Undo { game_id: u64, state: Board, black_tomove: bool, clock: GameClock },
+    /// the worker thread ends without waiting for the searches, engines are told to stop
+    Stop,
+    /// AIs with the ponder setting may think on the time of their opponent
+    Ponder(bool),
+    /// searches a move for the state with a Magister Ludi AI
+    Hint { state: Board },
+    /// the clocks stop, a running search goes on and its move is held until the game is resumed
+    Pause,
+    Resume,
+    /// the engine to move sends its best move at once, a Magister Ludi search cannot be interrupted
+    MoveNow,
+}
+
+/// events from the worker thread to the GUI
+pub enum WorkerEvent {
+    /// an AI chose the state
+    MovePlayed { game_id: u64, state: Board },
+    /// suggested state for the hint requested for `state`
+    Hint { state: Board, suggested: Board },
+    SearchInfo { game_id: u64, info: SearchInfo },
+    Error(String),
+}
+
+/// Magister Ludi player of the worker thread, the number of simulations is reduced to fit the clock
+struct MagisterPlayer {
+    magister: SidedMagister,
+    mcts_num: usize,
+    mcts_parallel: usize,
+    mcts_minimum: usize,
+    mcts_depth: usize,
+    /// simulations of the current Magister Ludi instance
+    current_num: usize,
+    /// measured thinking time per simulation
+    simulation_time: Option<f64>,
+}
+
+impl MagisterPlayer {
+    fn new(state: Board, black_tomove: bool, mcts_num: usize, mcts_parallel: usize, mcts_minimum: usize, mcts_depth: usize) -> Self {
+        Self {
+            magister: SidedMagister::new(state, black_tomove, mcts_num, mcts_parallel, mcts_minimum, mcts_depth),
+            mcts_num,
+            mcts_parallel,
+            mcts_minimum,
+            mcts_depth,
+            current_num: mcts_num,
+            simulation_time: None,
+        }
+    }
+
+    /// adjusts the number of simulations, so that the next move takes about the given time
+    fn fit_budget(&mut self, state: Board, black_tomove: bool, budget: time::Duration) {
+        let mcts_num = fit_simulations(self.mcts_num, self.simulation_time, budget);
+        if needs_new_instance(self.current_num, mcts_num) {
+            self.magister.stop_execution();
+            self.magister = SidedMagister::new(state, black_tomove, mcts_num, self.mcts_parallel, self.mcts_minimum, self.mcts_depth);
+            self.current_num = mcts_num;
+        }
+    }
+
+    fn own_move(&mut self) -> Board {
+        let started = time::Instant::now();
+        let chosen_state = self.magister.own_move();
+        self.simulation_time = Some(started.elapsed().as_secs_f64() / self.current_num as f64);
+        chosen_state
+    }
+}
+
+/// AI player of the worker thread
+enum WorkerPlayer {
+    Magister(MagisterPlayer),
+    Random(RandomPlayer),
+    Greedy,
+    /// the engine is told the position before every search, so it does not need to follow the moves
+    Engine(EngineProcess),
+}
+
+impl WorkerPlayer {
+    /// creates the player for the setting, humans do not need one
+    fn from_setting(setting: &PlayerSetting, state: Board, black_tomove: bool) -> Result<Option<Self>, String> {
+        match setting {
+            // remote players move through the controller
+            PlayerSetting::Human | PlayerSetting::Remote { .. } => Ok(None),
+            PlayerSetting::MagisterLudiAI { mcts_num, mcts_parallel, mcts_minimum, mcts_depth, .. } => {
+                Ok(Some(WorkerPlayer::Magister(MagisterPlayer::new(state, black_tomove, *mcts_num, *mcts_parallel, *mcts_minimum, *mcts_depth))))
+            }
+            PlayerSetting::Random => Ok(Some(WorkerPlayer::Random(RandomPlayer::default()))),
+            PlayerSetting::Greedy => Ok(Some(WorkerPlayer::Greedy)),
+            PlayerSetting::ExternalEngine { command, args } => Ok(Some(WorkerPlayer::Engine(EngineProcess::start(command, args)?))),
+        }
+    }
+
+    /// simulations planned for the next move, engines report their own
+    fn simulations(&self) -> usize {
+        match self {
+            WorkerPlayer::Magister(player) => player.current_num,
+            WorkerPlayer::Random(_) | WorkerPlayer::Greedy | WorkerPlayer::Engine(_) => 0,
+        }
+    }
+
+    fn fit_budget(&mut self, state: Board, black_tomove: bool, budget: time::Duration) {
+        if let WorkerPlayer::Magister(player) = self {
+            player.fit_budget(state, black_tomove, budget);
+        }
+    }
+
+    /// lets an engine stop its search, Magister Ludi searches cannot be interrupted
+    fn stop_handle(&self) -> Option<EngineStop> {
+        match self {
+            WorkerPlayer::Engine(engine) => Some(engine.stop_handle()),
+            _ => None,
+        }
+    }
+
+    fn own_move(
+        &mut self, state: Board, black_tomove: bool, budget: Option<time::Duration>, report: &dyn Fn(SearchInfo)
+    ) -> Result<Board, String> {
+        match self {
+            WorkerPlayer::Magister(player) => Ok(player.own_move()),
+            WorkerPlayer::Random(player) => player.choose(&MoveHistory::new_game(state, black_tomove))
+                .ok_or_else(|| "the random player cannot move".to_string()),
+            WorkerPlayer::Greedy => greedy_move(&MoveHistory::new_game(state, black_tomove))
+                .ok_or_else(|| "the greedy player cannot move".to_string()),
+            WorkerPlayer::Engine(engine) => engine.own_move(state, black_tomove, budget, report),
+        }
+    }
+
+    fn external_move(&mut self, state: Board) {
+        if let WorkerPlayer::Magister(player) = self {
+            player.magister.external_move(state);
+        }
+    }
+
+    /// engines are ended when they are dropped
+    fn stop(&mut self) {
+        if let WorkerPlayer::Magister(player) = self {
+            player.magister.stop_execution();
+        }
+    }
+}
+
+/// sends the events to the GUI and wakes it up
+#[derive(Clone)]
+struct EventSender {
+    events: Sender<WorkerEvent>,
+    repaint_ctx: Option<egui::Context>,
+}
+
+impl EventSender {
+    fn send(&self, event: WorkerEvent) {
+        // the GUI might already be gone
+        if self.events.send(event).is_ok() {
+            if let Some(ctx) = &self.repaint_ctx {
+                ctx.request_repaint();
+            }
+        }
+    }
+}
+
+/// result of a search thread
+struct SearchResult {
+    search_id: u64,
+    /// the player is given back after a full search, but not after a quick one
+    player: Option<WorkerPlayer>,
+    /// engines may fail to send a valid move
+    chosen_state: Result<Board, String>,
+    info: SearchInfo,
+}
+
+/// search of an AI on the time of its opponent, it answers the predicted move with its own search tree
+struct Ponder {
+    /// search id of the answer to the predicted move
+    search_id: u64,
+    /// game state after the predicted move of the opponent
+    predicted_state: Board,
+    /// answer found before the opponent moved
+    reply: Option<SearchResult>,
+}
+
+/// the AI players of one game, they search in their own threads, so the worker can always receive commands
+pub(crate) struct Worker {
+    events: EventSender,
+    /// hints are searched in their own threads and sent back to the worker
+    hint_sender: Sender<(Board, Board)>,
+    search_sender: Sender<SearchResult>,
+    game_id: u64,
+    black_ai: PlayerSetting,
+    white_ai: PlayerSetting,
+    /// the player is missing while it searches
+    black_player: Option<WorkerPlayer>,
+    white_player: Option<WorkerPlayer>,
+    is_blacksmove: bool,
+    game_state: Board,
+    /// the worker keeps its own copy of the clocks to budget the thinking time of the AIs
+    clock: GameClock,
+    /// a game is running and the AI to move has not started to search
+    move_pending: bool,
+    /// counts the searches, results of older searches are only used to get the player back
+    search_id: u64,
+    /// search whose move has not been sent yet, results of other searches are dropped
+    open_search: Option<u64>,
+    /// move of the open search that was found while the game was paused, it is sent when the game is resumed
+    held_move: Option<(Board, SearchInfo)>,
+    /// stops the engine of the open search
+    engine_stop: Option<EngineStop>,
+    paused: bool,
+    pondering_allowed: bool,
+    ponder: Option<Ponder>,
+}
+
+impl Worker {
+    /// the GUI of the context is repainted whenever an event is sent
+    pub(crate) fn spawn(
+        commands: Receiver<WorkerCommand>, events: Sender<WorkerEvent>, repaint_ctx: Option<egui::Context>
+    ) -> thread::JoinHandle<()> {
+        thread::spawn(move || {
+            let (hint_sender, hint_receiver) = unbounded();
+            let (search_sender, search_receiver) = unbounded();
+            let worker = Worker {
+                events: EventSender { events, repaint_ctx },
+                hint_sender,
+                search_sender,
+                game_id: 0,
+                black_ai: PlayerSetting::Human,
+                white_ai: PlayerSetting::Human,
+                black_player: None,
+                white_player: None,
+                is_blacksmove: true,
+                game_state: EMPTY_BOARD,
+                clock: GameClock::new(TimeControl::Unlimited),
+                move_pending: false,
+                search_id: 0,
+                open_search: None,
+                held_move: None,
+                engine_stop: None,
+                paused: false,
+                pondering_allowed: true,
+                ponder: None,
+            };
+            worker.run(commands, hint_receiver, search_receiver);
+        })
+    }
+
+    fn run(mut self, commands: Receiver<WorkerCommand>, hints: Receiver<(Board, Board)>, searches: Receiver<SearchResult>) {
+        loop {
+            // first the active player (if existant) will start to search
+            if self.move_pending && !self.paused {
+                self.start_search();
+            }
+            // wait for the GUI to adjust the move either from human hand or from the "active" AI who just sent a state
+            select! {
+                recv(commands) -> command => match command {
+                    Ok(command) => {
+                        if !self.handle(command) {
+                            break;
+                        }
+                    }
+                    // the controller is gone
+                    Err(_) => break,
+                },
+                recv(searches) -> result => {
+                    if let Ok(result) = result {
+                        self.search_finished(result);
+                    }
+                }
+                recv(hints) -> hint => {
+                    if let Ok((state, suggested)) = hint {
+                        self.events.send(WorkerEvent::Hint { state, suggested });
+                    }
+                }
+            }
+        }
+        // the searches are detached, a Magister Ludi search cannot be interrupted and would keep the GUI waiting,
+        // it ends on its own and its result is dropped with the channel
+        self.stop_players();
+    }
+
+    /// returns false if the worker has to stop
+    fn handle(&mut self, command: WorkerCommand) -> bool {
+        match command {
+            WorkerCommand::NewGame { game_id, state, black_tomove, black_ai, white_ai, clock } => {
+                self.black_ai = black_ai;
+                self.white_ai = white_ai;
+                self.start(game_id, state, black_tomove, clock);
+            }
+            WorkerCommand::Undo { game_id, state, black_tomove, clock } => {
+                self.start(game_id, state, black_tomove, clock);
+            }
+            WorkerCommand::MovePlayed { state, game_ended } => self.move_played(state, game_ended),
+            WorkerCommand::Stop => return false,
+            WorkerCommand::Ponder(allowed) => {
+                self.pondering_allowed = allowed;
+                if !allowed {
+                    self.abandon_ponder();
+                }
+            }
+            WorkerCommand::Hint { state } => self.search_hint(state),
+            WorkerCommand::Pause => {
+                self.paused = true;
+                self.clock.stop();
+            }
+            WorkerCommand::Resume => {
+                self.paused = false;
+                if !self.clock.is_unlimited() {
+                    self.clock.start(self.is_blacksmove);
+                }
+                if let Some((chosen_state, info)) = self.held_move.take() {
+                    self.send_move(chosen_state, info);
+                }
+            }
+            WorkerCommand::MoveNow => {
+                if let Some(engine_stop) = &self.engine_stop {
+                    engine_stop.stop();
+                }
+            }
+        }
+        true
+    }
+
+    /// creates the players for the state, the AI to move starts thinking right away
+    fn start(&mut self, game_id: u64, state: Board, black_tomove: bool, clock: GameClock) {
+        self.stop_players();
+        self.game_id = game_id;
+        self.game_state = state;
+        self.is_blacksmove = black_tomove;
+        self.clock = clock;
+        self.paused = false;
+        self.black_player = self.create_player(true, state);
+        self.white_player = self.create_player(false, state);
+        self.move_pending = self.current_player().is_some();
+        self.start_ponder();
+    }
+
+    /// the player for the setting of the color, it starts from the state with the side to move of the worker,
+    /// the GUI is told if it cannot be created
+    fn create_player(&self, for_black: bool, state: Board) -> Option<WorkerPlayer> {
+        let setting = if for_black {&self.black_ai} else {&self.white_ai};
+        WorkerPlayer::from_setting(setting, state, self.is_blacksmove).unwrap_or_else(|msg| {
+            self.events.send(WorkerEvent::Error(msg));
+            None
+        })
+    }
+
+    fn current_player(&mut self) -> &mut Option<WorkerPlayer> {
+        if self.is_blacksmove {&mut self.black_player} else {&mut self.white_player}
+    }
+
+    /// hands the player to move over to a search thread
+    fn start_search(&mut self) {
+        self.move_pending = false;
+        let budget = self.clock.move_budget(self.is_blacksmove);
+        // the player of an abandoned search is created again
+        let Some(mut activegister) = self.current_player().take().or_else(|| self.create_player(self.is_blacksmove, self.game_state)) else {
+            return;
+        };
+        if let Some(budget) = budget {
+            activegister.fit_budget(self.game_state, self.is_blacksmove, budget);
+        }
+        let info = SearchInfo {
+            for_black: self.is_blacksmove,
+            simulations: activegister.simulations(),
+            ..SearchInfo::default()
+        };
+        self.events.send(WorkerEvent::SearchInfo { game_id: self.game_id, info: info.clone() });
+        self.search_id += 1;
+        self.open_search = Some(self.search_id);
+        self.engine_stop = activegister.stop_handle();
+        let search_id = self.search_id;
+        let search_sender = self.search_sender.clone();
+        let (game_id, state, black_tomove, events) = (self.game_id, self.game_state, self.is_blacksmove, self.events.clone());
+        thread::spawn(move || {
+            let started = time::Instant::now();
+            // engines report their statistics while they search
+            let last_info = RefCell::new(info);
+            let report = |info: SearchInfo| {
+                events.send(WorkerEvent::SearchInfo { game_id, info: info.clone() });
+                *last_info.borrow_mut() = info;
+            };
+            let chosen_state = activegister.own_move(state, black_tomove, budget, &report);
+            let info = SearchInfo { elapsed: started.elapsed(), finished: true, ..last_info.into_inner() };
+            let _ = search_sender.send(SearchResult { search_id, player: Some(activegister), chosen_state, info });
+        });
+    }
+
+    fn search_finished(&mut self, result: SearchResult) {
+        let is_open = self.open_search == Some(result.search_id);
+        if let Some(ponder) = self.ponder.as_mut() {
+            if ponder.search_id == result.search_id && !is_open {
+                // the opponent has not moved yet
+                ponder.reply = Some(result);
+                return;
+            }
+        }
+        if let Some(mut player) = result.player {
+            if is_open && result.chosen_state.is_ok() {
+                // the player thinks about the move it chose
+                *self.current_player() = Some(player);
+            } else {
+                // the player of an older game, a move that was already played or a failed engine,
+                // it is created again when needed
+                player.stop();
+            }
+        }
+        if !is_open {
+            return;
+        }
+        self.open_search = None;
+        self.engine_stop = None;
+        let chosen_state = match result.chosen_state {
+            Ok(chosen_state) => chosen_state,
+            Err(msg) => {
+                self.events.send(WorkerEvent::Error(msg));
+                return;
+            }
+        };
+        if self.paused {
+            self.held_move = Some((chosen_state, result.info));
+            return;
+        }
+        self.send_move(chosen_state, result.info);
+    }
+
+    fn send_move(&self, chosen_state: Board, info: SearchInfo) {
+        self.events.send(WorkerEvent::SearchInfo { game_id: self.game_id, info });
+        self.events.send(WorkerEvent::MovePlayed { game_id: self.game_id, state: chosen_state });
+    }
+
+    fn move_played(&mut self, state: Board, game_ended: bool) {
+        self.game_state = state;
+        if !self.clock.is_unlimited() {
+            self.clock.press(self.is_blacksmove);
+        }
+        // now a move was made and roles will be switched
+        self.is_blacksmove = !self.is_blacksmove;
+        if game_ended {
+            self.stop_players();
+            return;
+        }
+        if let Some(ponder) = self.ponder.take_if(|ponder| ponder.predicted_state == state) {
+            // the predicted move was played, the ponder search goes on as the search of the player to move
+            self.open_search = Some(ponder.search_id);
+            self.move_pending = false;
+            if let Some(reply) = ponder.reply {
+                self.search_finished(reply);
+            }
+            return;
+        }
+        // the answer to another move is not needed
+        self.abandon_ponder();
+        // the player who has to move now was waiting
+        match self.current_player() {
+            Some(waitgister) => waitgister.external_move(state),
+            // the player of a missed ponder search followed another move, it is created again at once
+            None => *self.current_player() = self.create_player(self.is_blacksmove, state),
+        }
+        self.move_pending = self.current_player().is_some();
+        self.start_ponder();
+    }
+
+    /// lets a waiting AI answer the move the greedy player predicts for the human to move,
+    /// it searches with its own tree, so the search goes on if the prediction was right
+    fn start_ponder(&mut self) {
+        self.abandon_ponder();
+        let opponent_is_black = self.is_blacksmove;
+        let (setting, opponent_setting) = if opponent_is_black {(&self.white_ai, &self.black_ai)} else {(&self.black_ai, &self.white_ai)};
+        if !self.pondering_allowed || *opponent_setting != PlayerSetting::Human
+            || !matches!(setting, PlayerSetting::MagisterLudiAI { ponder: true, .. }) {
+            return;
+        }
+        let Some(mut predicted_state) = greedy_move(&MoveHistory::new_game(self.game_state, opponent_is_black)) else {
+            return;
+        };
+        // the board is turned after every move of the blue player, just as the GUI does
+        if opponent_is_black {
+            predicted_state = AbaloneGame::rotate_board(predicted_state);
+        }
+        let budget = self.clock.move_budget(!opponent_is_black);
+        let ponder_player = if opponent_is_black {&mut self.white_player} else {&mut self.black_player};
+        let Some(mut pondergister) = ponder_player.take() else {
+            return;
+        };
+        if let Some(budget) = budget {
+            pondergister.fit_budget(self.game_state, opponent_is_black, budget);
+        }
+        self.search_id += 1;
+        let search_id = self.search_id;
+        self.ponder = Some(Ponder { search_id, predicted_state, reply: None });
+        let info = SearchInfo { for_black: !opponent_is_black, simulations: pondergister.simulations(), ..SearchInfo::default() };
+        let search_sender = self.search_sender.clone();
+        thread::spawn(move || {
+            pondergister.external_move(predicted_state);
+            let started = time::Instant::now();
+            let chosen_state = pondergister.own_move(predicted_state, !opponent_is_black, None, &|_| {});
+            let info = SearchInfo { elapsed: started.elapsed(), finished: true, ..info };
+            let _ = search_sender.send(SearchResult { search_id, player: Some(pondergister), chosen_state, info });
+        });
+    }
+
+    /// lets a Magister Ludi AI search the best move for the state in the background,
+    /// the side to move searches with its own parameters, humans with the AI of the start window
+    fn search_hint(&mut self, state: Board) {
+        let setting = if self.is_blacksmove {&self.black_ai} else {&self.white_ai};
+        let setting = if matches!(setting, PlayerSetting::MagisterLudiAI { .. }) {setting.clone()} else {PlayerSetting::default_ai()};
+        let PlayerSetting::MagisterLudiAI { mcts_num, mcts_parallel, mcts_minimum, mcts_depth, .. } = setting else {
+            return;
+        };
+        let (hint_sender, black_tomove) = (self.hint_sender.clone(), self.is_blacksmove);
+        thread::spawn(move || {
+            let mut magister = SidedMagister::new(state, black_tomove, mcts_num, mcts_parallel, mcts_minimum, mcts_depth);
+            let suggested = magister.own_move();
+            magister.stop_execution();
+            let _ = hint_sender.send((state, suggested));
+        });
+    }
+
+    fn stop_players(&mut self) {
+        for player in [self.black_player.take(), self.white_player.take()].iter_mut().flatten() {
+            player.stop();
+        }
+        // a searching engine would keep its thread waiting
+        if let Some(engine_stop) = self.engine_stop.take() {
+            engine_stop.stop();
+        }
+        // the running searches end on their own, their results are dropped
+        self.open_search = None;
+        self.held_move = None;
+        self.abandon_ponder();
+        self.move_pending = false;
+    }
+
+    /// the answer to a move that was not played is not needed, a running ponder search ends on its own
+    /// and its player is stopped when it is done
+    fn abandon_ponder(&mut self) {
+        if let Some(Ponder { reply: Some(SearchResult { player: Some(mut player), .. }), .. }) = self.ponder.take() {
+            player.stop();
+        }
+    }
+}